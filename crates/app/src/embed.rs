@@ -0,0 +1,641 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    pin::pin,
+    time::Duration,
+};
+
+use domain::{
+    chunk::Chunk,
+    ids::{ChunkId, ContentHash},
+};
+use embedding::{EmbedError, Embedder};
+use futures::{Stream, StreamExt, TryFutureExt, TryStreamExt, stream};
+
+use crate::{App, AppError, Result};
+
+/// Upper bound on any single wait between batch retries.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How [`App::embed_pending`] works through the chunks without embeddings.
+///
+/// Single requests are retried by the embedder, e.g. per
+/// [`embedding::OpenAiConfig::max_retries`]. A batch that still fails with
+/// a transient provider or database error is retried here as a whole,
+/// before the run gives up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbedConfig {
+    /// Chunks per embedding request; the model's own batch limit when unset.
+    pub batch_size: Option<usize>,
+    /// Batches embedded and stored at the same time.
+    pub concurrency: usize,
+    /// How many times a batch that failed with a retryable error is
+    /// embedded and stored again.
+    pub max_retries: u32,
+    /// Wait before the first batch retry, doubled for each one after it.
+    pub initial_backoff: Duration,
+}
+
+impl Default for EmbedConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: None,
+            concurrency: 4,
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl EmbedConfig {
+    #[must_use]
+    pub const fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_BACKOFF)
+    }
+}
+
+/// Where an embedding run stands.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EmbedProgress {
+    /// Chunks that have an embedding from the model, including those
+    /// embedded before this run and those given a cached vector.
+    pub done: u64,
+    /// Chunks still waiting for one.
+    pub pending: u64,
+    /// Chunks skipped in this run because the model rejected them as too
+    /// large. They stay pending.
+    pub failed: u64,
+    /// Chunks in this run whose text was already embedded, either in the
+    /// cache or earlier in the same batch.
+    pub cache_hits: u64,
+    /// Texts in this run that had to be sent to the model.
+    pub cache_misses: u64,
+}
+
+impl EmbedProgress {
+    /// Share of the chunks embedded in this run that did not need the
+    /// model, if any were embedded.
+    #[must_use]
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let total = self.cache_hits + self.cache_misses;
+        (total > 0).then(|| self.cache_hits as f64 / total as f64)
+    }
+}
+
+/// What embedding one batch achieved.
+#[derive(Debug, Default)]
+struct BatchOutcome {
+    stored: u64,
+    failed: u64,
+    cache_hits: u64,
+    cache_misses: u64,
+}
+
+impl App {
+    /// Embeds every chunk that has no embedding from the configured model
+    /// yet, in batches, and stores the vectors. Texts the model has
+    /// embedded before are taken from the embedding cache. `on_progress`
+    /// is called once up front and after every batch.
+    ///
+    /// Each batch is stored as soon as it is embedded, so an interrupted
+    /// run loses at most the batches in flight, and the next run resumes
+    /// with the chunks still missing.
+    ///
+    /// Chunks of one page that share a text go in the same batch, so the
+    /// text is embedded once. A text repeated in the next page can still be
+    /// sent again if that page's batches start before the first copy is
+    /// cached.
+    pub async fn embed_pending(
+        &self,
+        config: EmbedConfig,
+        mut on_progress: impl FnMut(&EmbedProgress),
+    ) -> Result<EmbedProgress> {
+        let embedder = self.embedder()?;
+        let model = embedder.model_id();
+        let batch_size = config
+            .batch_size
+            .unwrap_or_else(|| embedder.max_batch_size())
+            .max(1);
+        let concurrency = config.concurrency.max(1);
+
+        let (done, pending) = self.store.embedding_counts(model).await?;
+        let mut progress = EmbedProgress {
+            done,
+            pending,
+            ..EmbedProgress::default()
+        };
+        on_progress(&progress);
+
+        // Pages are fetched ahead of the batches in flight by id, so no
+        // chunk is handed out twice while earlier batches are still running.
+        let page_size = batch_size * concurrency;
+        let pages = keyset_pages(page_size, |after| {
+            self.store
+                .chunks_missing_embeddings(model, after, page_size)
+                .err_into()
+        });
+
+        let batches = pages
+            .map_ok(|page| stream::iter(batches(page, batch_size)).map(Ok))
+            .try_flatten()
+            .map_ok(
+                |batch| async move { retry(&config, || self.embed_batch(embedder, &batch)).await },
+            )
+            .try_buffer_unordered(concurrency);
+        let mut batches = pin!(batches);
+
+        while let Some(outcome) = batches.try_next().await? {
+            progress.done += outcome.stored;
+            progress.pending = progress.pending.saturating_sub(outcome.stored);
+            progress.failed += outcome.failed;
+            progress.cache_hits += outcome.cache_hits;
+            progress.cache_misses += outcome.cache_misses;
+            on_progress(&progress);
+        }
+
+        Ok(progress)
+    }
+
+    /// Embeds and stores one batch. Chunks whose text is in the embedding
+    /// cache take the cached vector; the rest are embedded once per
+    /// distinct text and their vectors cached.
+    async fn embed_batch(&self, embedder: &dyn Embedder, batch: &[Chunk]) -> Result<BatchOutcome> {
+        let keyed: Vec<(ChunkId, ContentHash)> = batch
+            .iter()
+            .map(|chunk| (chunk.id(), ContentHash::from_content(chunk.text())))
+            .collect();
+        let cached: HashSet<ChunkId> = self
+            .store
+            .embed_from_cache(embedder.model_id(), embedder.dimension(), &keyed)
+            .await?
+            .into_iter()
+            .collect();
+
+        let missing: Vec<(Chunk, ContentHash)> = batch
+            .iter()
+            .zip(keyed)
+            .filter(|(chunk, _)| !cached.contains(&chunk.id()))
+            .map(|(chunk, (_, hash))| (chunk.clone(), hash))
+            .collect();
+
+        let embedded = embed_distinct(embedder, &missing).await?;
+        let model = embedder.model_id();
+        self.store
+            .cache_embeddings(model, &embedded.by_text)
+            .await?;
+        let stored = self
+            .store
+            .upsert_embeddings(model, &embedded.by_chunk)
+            .await?;
+
+        let mut outcome = embedded.outcome();
+        outcome.stored = stored + cached.len() as u64;
+        outcome.cache_hits += cached.len() as u64;
+        Ok(outcome)
+    }
+}
+
+/// Runs `op`, running it again after a growing pause while it fails with a
+/// retryable error, up to `config.max_retries` times. A rate limit's
+/// `Retry-After` is waited out if it is longer than the pause.
+async fn retry<T, F, Fut>(config: &EmbedConfig, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Err(err) if err.is_retryable() && attempt < config.max_retries => {
+                let mut delay = config.backoff(attempt);
+                if let AppError::Embed(EmbedError::RateLimited {
+                    retry_after: Some(retry_after),
+                }) = err
+                {
+                    delay = delay.max(retry_after.min(MAX_BACKOFF));
+                }
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Pages through chunks in id order, asking `fetch` for the page after the
+/// last id of the previous one, until a page comes back short.
+fn keyset_pages<F, Fut>(page_size: usize, fetch: F) -> impl Stream<Item = Result<Vec<Chunk>>>
+where
+    F: Fn(Option<ChunkId>) -> Fut,
+    Fut: Future<Output = Result<Vec<Chunk>>>,
+{
+    stream::try_unfold(Some(None), move |after: Option<Option<ChunkId>>| {
+        let page = after.map(&fetch);
+        async move {
+            let Some(page) = page else {
+                return Ok(None);
+            };
+            let page = page.await?;
+            let next = (page.len() == page_size).then(|| page.last().map(Chunk::id));
+            Ok(Some((page, next)))
+        }
+    })
+}
+
+/// Splits a page into batches of at most `batch_size` distinct texts,
+/// keeping all chunks with the same text in one batch.
+fn batches(page: Vec<Chunk>, batch_size: usize) -> Vec<Vec<Chunk>> {
+    let mut groups: Vec<Vec<Chunk>> = Vec::new();
+    let mut by_text: HashMap<ContentHash, usize> = HashMap::new();
+    for chunk in page {
+        let group = *by_text
+            .entry(ContentHash::from_content(chunk.text()))
+            .or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+        groups[group].push(chunk);
+    }
+    groups
+        .chunks(batch_size)
+        .map(<[Vec<Chunk>]>::concat)
+        .collect()
+}
+
+/// Vectors for a batch of chunks, ready to store.
+#[derive(Debug, Default)]
+struct EmbeddedChunks {
+    /// One vector per embedded chunk.
+    by_chunk: Vec<(ChunkId, Vec<f32>)>,
+    /// One vector per distinct text, for the cache.
+    by_text: Vec<(ContentHash, Vec<f32>)>,
+    /// Chunks the model rejected as too large.
+    failed: u64,
+}
+
+impl EmbeddedChunks {
+    /// Counts every distinct text sent to the model as a miss and every
+    /// other embedded chunk sharing its text as a hit.
+    fn outcome(&self) -> BatchOutcome {
+        BatchOutcome {
+            stored: 0,
+            failed: self.failed,
+            cache_hits: (self.by_chunk.len() - self.by_text.len()) as u64,
+            cache_misses: self.by_text.len() as u64,
+        }
+    }
+}
+
+/// Embeds each distinct text among `chunks` once and gives every chunk its
+/// text's vector. Chunks whose text the model rejects as too large are
+/// counted as failed instead.
+async fn embed_distinct(
+    embedder: &dyn Embedder,
+    chunks: &[(Chunk, ContentHash)],
+) -> embedding::Result<EmbeddedChunks> {
+    let mut distinct: HashMap<&ContentHash, usize> = HashMap::new();
+    let mut texts = Vec::new();
+    let mut hashes = Vec::new();
+    for (chunk, hash) in chunks {
+        distinct.entry(hash).or_insert_with(|| {
+            texts.push(chunk.text());
+            hashes.push(hash.clone());
+            texts.len() - 1
+        });
+    }
+
+    let vectors = embed_texts(embedder, &texts).await?;
+
+    let mut embedded = EmbeddedChunks::default();
+    for (chunk, hash) in chunks {
+        match &vectors[distinct[hash]] {
+            Some(vector) => embedded.by_chunk.push((chunk.id(), vector.clone())),
+            None => embedded.failed += 1,
+        }
+    }
+    embedded.by_text = hashes
+        .into_iter()
+        .zip(vectors)
+        .filter_map(|(hash, vector)| Some((hash, vector?)))
+        .collect();
+    Ok(embedded)
+}
+
+/// Embeds `texts` in one request. If the model rejects it as too large,
+/// each text is sent on its own, so one oversized text does not hold back
+/// the rest; texts too large on their own get `None`.
+async fn embed_texts(
+    embedder: &dyn Embedder,
+    texts: &[&str],
+) -> embedding::Result<Vec<Option<Vec<f32>>>> {
+    if texts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    match embedder.embed(texts).await {
+        Ok(vectors) => Ok(vectors.into_iter().map(Some).collect()),
+        Err(EmbedError::InputTooLarge(_)) if texts.len() > 1 => {
+            let mut vectors = Vec::with_capacity(texts.len());
+            for text in texts {
+                vectors.push(match embedder.embed(&[text]).await {
+                    Ok(mut vector) => vector.pop(),
+                    Err(EmbedError::InputTooLarge(_)) => None,
+                    Err(err) => return Err(err),
+                });
+            }
+            Ok(vectors)
+        }
+        Err(EmbedError::InputTooLarge(_)) => Ok(vec![None]),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use domain::ids::ObservationId;
+
+    use super::*;
+
+    /// Embeds each text as its length, rejecting any request that holds a
+    /// text longer than `max_len`, and records the requests it gets.
+    struct ScriptedEmbedder {
+        max_len: usize,
+        requests: Mutex<Vec<Vec<String>>>,
+    }
+
+    impl ScriptedEmbedder {
+        const fn new(max_len: usize) -> Self {
+            Self {
+                max_len,
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn requests(&self) -> Vec<Vec<String>> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl Embedder for ScriptedEmbedder {
+        fn model_id(&self) -> &str {
+            "scripted"
+        }
+
+        fn dimension(&self) -> usize {
+            1
+        }
+
+        async fn embed(&self, texts: &[&str]) -> embedding::Result<Vec<Vec<f32>>> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push(texts.iter().map(|t| (*t).to_string()).collect());
+            if texts.iter().any(|t| t.len() > self.max_len) {
+                return Err(EmbedError::InputTooLarge("too long".to_string()));
+            }
+            Ok(texts.iter().map(|t| vec![t.len() as f32]).collect())
+        }
+    }
+
+    fn chunk(text: &str) -> (Chunk, ContentHash) {
+        let chunk = Chunk::reconstruct(
+            ChunkId::new(),
+            ObservationId::new(),
+            0,
+            text.to_string(),
+            0,
+            text.len(),
+            1,
+        );
+        (chunk, ContentHash::from_content(text))
+    }
+
+    #[tokio::test]
+    async fn pages_by_the_last_id_until_a_short_page() {
+        for (total, pages) in [(5, vec![2, 2, 1]), (4, vec![2, 2, 0])] {
+            let chunks: Vec<Chunk> = (0..total).map(|i| chunk(&i.to_string()).0).collect();
+            let afters = Mutex::new(Vec::new());
+
+            let fetched: Vec<Vec<Chunk>> = keyset_pages(2, |after| {
+                afters.lock().unwrap().push(after);
+                let start = after.map_or(0, |id| {
+                    chunks.iter().position(|c| c.id() == id).unwrap() + 1
+                });
+                let page = chunks.iter().skip(start).take(2).cloned().collect();
+                async move { Ok(page) }
+            })
+            .try_collect()
+            .await
+            .unwrap();
+
+            let sizes: Vec<usize> = fetched.iter().map(Vec::len).collect();
+            assert_eq!(sizes, pages);
+            let ids: Vec<ChunkId> = fetched.iter().flatten().map(Chunk::id).collect();
+            assert_eq!(ids, chunks.iter().map(Chunk::id).collect::<Vec<_>>());
+            assert_eq!(
+                *afters.lock().unwrap(),
+                [None, Some(chunks[1].id()), Some(chunks[3].id())]
+            );
+        }
+    }
+
+    #[test]
+    fn batches_keep_repeated_texts_together() {
+        let page: Vec<Chunk> = ["a", "b", "a", "c", "b", "d"]
+            .into_iter()
+            .map(|text| chunk(text).0)
+            .collect();
+
+        let batches = batches(page, 2);
+        let texts: Vec<Vec<&str>> = batches
+            .iter()
+            .map(|batch| batch.iter().map(Chunk::text).collect())
+            .collect();
+        assert_eq!(texts, [vec!["a", "a", "b", "b"], vec!["c", "d"]]);
+    }
+
+    #[tokio::test]
+    async fn embeds_each_distinct_text_once_and_counts_repeats_as_hits() {
+        let embedder = ScriptedEmbedder::new(usize::MAX);
+        let chunks = [
+            chunk("crab"),
+            chunk("shell"),
+            chunk("crab"),
+            chunk("crab"),
+            chunk("sand"),
+        ];
+
+        let embedded = embed_distinct(&embedder, &chunks).await.unwrap();
+        let outcome = embedded.outcome();
+
+        assert_eq!(embedder.requests(), [vec!["crab", "shell", "sand"]]);
+        assert_eq!(embedded.by_chunk.len(), 5);
+        assert_eq!(embedded.by_chunk[3], (chunks[3].0.id(), vec![4.0]));
+        assert_eq!(embedded.by_text.len(), 3);
+        assert_eq!((outcome.cache_hits, outcome.cache_misses), (2, 3));
+
+        let progress = EmbedProgress {
+            cache_hits: outcome.cache_hits,
+            cache_misses: outcome.cache_misses,
+            ..EmbedProgress::default()
+        };
+        assert_eq!(progress.cache_hit_rate(), Some(0.4));
+        assert_eq!(EmbedProgress::default().cache_hit_rate(), None);
+    }
+
+    #[tokio::test]
+    async fn failed_repeats_are_neither_hits_nor_misses() {
+        let embedder = ScriptedEmbedder::new(4);
+        let long = "too long";
+        let chunks = [chunk(long), chunk("ok"), chunk(long)];
+
+        let embedded = embed_distinct(&embedder, &chunks).await.unwrap();
+        let outcome = embedded.outcome();
+
+        assert_eq!(embedded.failed, 2);
+        assert_eq!((outcome.cache_hits, outcome.cache_misses), (0, 1));
+    }
+
+    #[tokio::test]
+    async fn splits_a_rejected_batch_and_fails_only_oversized_chunks() {
+        let embedder = ScriptedEmbedder::new(10);
+        let long = "far too long to embed";
+        let chunks = [chunk("short"), chunk(long), chunk("also ok")];
+
+        let embedded = embed_distinct(&embedder, &chunks).await.unwrap();
+
+        assert_eq!(
+            embedder.requests(),
+            [
+                vec!["short", long, "also ok"],
+                vec!["short"],
+                vec![long],
+                vec!["also ok"]
+            ]
+        );
+        assert_eq!(
+            embedded.by_chunk,
+            [(chunks[0].0.id(), vec![5.0]), (chunks[2].0.id(), vec![7.0])]
+        );
+        assert_eq!(embedded.failed, 1);
+        assert_eq!(embedded.by_text.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_with_backoff() {
+        let config = EmbedConfig::default()
+            .with_max_retries(3)
+            .with_initial_backoff(Duration::from_millis(1));
+        assert_eq!(config.backoff(2), Duration::from_millis(4));
+        assert_eq!(
+            EmbedConfig::default().backoff(10),
+            MAX_BACKOFF,
+            "backoff is capped"
+        );
+
+        let busy = || {
+            AppError::Embed(EmbedError::Provider {
+                status: 503,
+                message: "busy".to_string(),
+            })
+        };
+
+        let attempts = Mutex::new(0);
+        let result = retry(&config, || {
+            let attempt = {
+                let mut attempts = attempts.lock().unwrap();
+                *attempts += 1;
+                *attempts
+            };
+            async move {
+                if attempt < 3 {
+                    Err(busy())
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+
+        let attempts = Mutex::new(0);
+        let result: Result<()> = retry(&config, || {
+            *attempts.lock().unwrap() += 1;
+            async { Err(busy()) }
+        })
+        .await;
+        assert!(result.unwrap_err().is_retryable());
+        assert_eq!(*attempts.lock().unwrap(), 4, "the first try and 3 retries");
+
+        let attempts = Mutex::new(0);
+        let result: Result<()> = retry(&config, || {
+            *attempts.lock().unwrap() += 1;
+            async { Err(AppError::Embed(EmbedError::InputTooLarge("big".into()))) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(
+            *attempts.lock().unwrap(),
+            1,
+            "permanent errors are not retried"
+        );
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_not_split() {
+        struct Unavailable;
+
+        #[async_trait]
+        impl Embedder for Unavailable {
+            fn model_id(&self) -> &str {
+                "unavailable"
+            }
+
+            fn dimension(&self) -> usize {
+                1
+            }
+
+            async fn embed(&self, _texts: &[&str]) -> embedding::Result<Vec<Vec<f32>>> {
+                Err(EmbedError::Provider {
+                    status: 503,
+                    message: "busy".to_string(),
+                })
+            }
+        }
+
+        let result = embed_distinct(&Unavailable, &[chunk("a"), chunk("b")]).await;
+        assert!(matches!(
+            result,
+            Err(EmbedError::Provider { status: 503, .. })
+        ));
+    }
+}
@@ -0,0 +1,39 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, AppError>;
+
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error(transparent)]
+    Store(#[from] store::StoreError),
+
+    #[error(transparent)]
+    Ingest(#[from] ingest::IngestError),
+
+    #[error(transparent)]
+    Domain(#[from] domain::error::Error),
+
+    #[error(transparent)]
+    Embed(#[from] embedding::EmbedError),
+
+    #[error(transparent)]
+    Rag(#[from] rag::RagError),
+
+    #[error("no embedding model is configured")]
+    NoEmbedder,
+
+    #[error("no chat model is configured")]
+    NoLlm,
+}
+
+impl AppError {
+    /// Whether the operation may succeed if tried again later.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Store(err) => err.is_retryable(),
+            Self::Embed(err) => err.is_retryable(),
+            _ => false,
+        }
+    }
+}
@@ -1,22 +1,122 @@
+pub mod embed;
+pub mod error;
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+use chrono::Utc;
 use domain::{
     chunk::Chunk,
-    ids::ObservationId,
+    chunker::ChunkOptions,
+    feed::{Feed, FeedPollState, FeedPollStatus},
+    ids::{FeedId, ObservationId},
     observation::{Observation, SourceKind},
+    search::{SearchHit, SearchQuery},
+    tokenizer::{HeuristicTokenizer, Tokenizer},
+};
+use embedding::Embedder;
+use ingest::{FeedIngester, FeedSource, FetchOutcome, WebIngester, parse_feed};
+use rag::{
+    Answer, AnswerConfig, Answerer, HybridRetriever, LlmClient, Reranker, RetrievedChunk,
+    RetrieverConfig,
 };
-use store::{PgStore, Result};
+use store::PgStore;
+
+pub use crate::{
+    embed::{EmbedConfig, EmbedProgress},
+    error::{AppError, Result},
+};
+
+/// Outcome of storing a batch of ingested observations.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IngestReport {
+    pub inserted: usize,
+    pub existing: usize,
+    pub skipped: usize,
+}
+
+/// Outcome of polling a single feed subscription.
+#[derive(Debug, Clone)]
+pub struct FeedPollReport {
+    pub feed_id: FeedId,
+    pub url: String,
+    pub status: FeedPollStatus,
+    pub new_observations: usize,
+    pub error: Option<String>,
+}
+
+/// Outcome of re-chunking observations chunked with outdated options.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RechunkReport {
+    pub observations: usize,
+    pub chunks: usize,
+}
+
+/// What a successful poll fetched, before it is recorded against the feed.
+enum PolledFeed {
+    NotModified,
+    Fetched {
+        title: Option<String>,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        new_observations: usize,
+    },
+}
 
 pub struct App {
-    store: PgStore,
+    store: Arc<PgStore>,
+    tokenizer: Arc<dyn Tokenizer>,
+    embedder: Option<Arc<dyn Embedder>>,
+    llm: Option<Arc<dyn LlmClient>>,
+    reranker: Option<Arc<dyn Reranker>>,
 }
 
 impl App {
     pub async fn connect(database_url: &str) -> Result<Self> {
         let store = PgStore::connect(database_url).await?;
-        Ok(Self { store })
+        Ok(Self {
+            store: Arc::new(store),
+            tokenizer: Arc::new(HeuristicTokenizer),
+            embedder: None,
+            llm: None,
+            reranker: None,
+        })
+    }
+
+    /// Replaces the default heuristic tokenizer used for chunk token counts.
+    #[must_use]
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Sets the model used to embed chunks and queries.
+    #[must_use]
+    pub fn with_embedder(mut self, embedder: Arc<dyn Embedder>) -> Self {
+        self.embedder = Some(embedder);
+        self
+    }
+
+    /// Sets the chat model used to answer questions.
+    #[must_use]
+    pub fn with_llm(mut self, llm: Arc<dyn LlmClient>) -> Self {
+        self.llm = Some(llm);
+        self
+    }
+
+    /// Sets the model that reorders hybrid search results before they are
+    /// returned or used to answer questions.
+    #[must_use]
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.reranker = Some(reranker);
+        self
+    }
+
+    fn embedder(&self) -> Result<&dyn Embedder> {
+        self.embedder.as_deref().ok_or(AppError::NoEmbedder)
     }
 
     pub async fn migrate(&self) -> Result<()> {
-        self.store.migrate().await
+        Ok(self.store.migrate().await?)
     }
 
     pub async fn ingest_text(
@@ -38,28 +138,257 @@ impl App {
         }
 
         let observation = builder.build()?;
-        self.store.upsert_observation(&observation).await
+        Ok(self.store.upsert_observation(&observation).await?)
+    }
+
+    pub async fn ingest_feed(&self, source: &FeedSource) -> Result<IngestReport> {
+        let parsed = FeedIngester::new()?.ingest(source).await?;
+
+        let mut report = IngestReport {
+            skipped: parsed.skipped,
+            ..IngestReport::default()
+        };
+
+        for observation in &parsed.observations {
+            let (_, inserted) = self.store.upsert_observation(observation).await?;
+            if inserted {
+                report.inserted += 1;
+            } else {
+                report.existing += 1;
+            }
+        }
+
+        Ok(report)
+    }
+
+    pub async fn ingest_url(&self, url: &str) -> Result<(ObservationId, bool)> {
+        let observation = WebIngester::new()?.ingest(url).await?;
+        Ok(self.store.upsert_observation(&observation).await?)
+    }
+
+    pub async fn ingest_pdf(&self, path: &Path) -> Result<(ObservationId, bool)> {
+        let observation = ingest::load_pdf(path)?;
+        Ok(self.store.upsert_observation(&observation).await?)
+    }
+
+    pub async fn add_feed(&self, url: &str, poll_interval: Duration) -> Result<(FeedId, bool)> {
+        let feed = Feed::new(url, poll_interval)?;
+        Ok(self.store.add_feed(&feed).await?)
+    }
+
+    pub async fn list_feeds(&self) -> Result<Vec<Feed>> {
+        Ok(self.store.list_feeds().await?)
+    }
+
+    pub async fn remove_feed(&self, id: FeedId) -> Result<bool> {
+        Ok(self.store.remove_feed(id).await?)
+    }
+
+    /// Polls every feed that is due (or every feed, with `force`), using
+    /// conditional GET so unchanged feeds cost a single `304` round trip.
+    /// A failing feed is recorded and does not stop the others.
+    pub async fn poll_feeds(&self, force: bool) -> Result<Vec<FeedPollReport>> {
+        let feeds = if force {
+            self.store.list_feeds().await?
+        } else {
+            self.store.due_feeds(Utc::now()).await?
+        };
+
+        let ingester = FeedIngester::new()?;
+        let mut reports = Vec::with_capacity(feeds.len());
+
+        for feed in &feeds {
+            reports.push(self.poll_feed(&ingester, feed).await?);
+        }
+
+        Ok(reports)
+    }
+
+    async fn poll_feed(&self, ingester: &FeedIngester, feed: &Feed) -> Result<FeedPollReport> {
+        let polled_at = Utc::now();
+        let mut state = FeedPollState {
+            last_polled_at: Some(polled_at),
+            next_poll_at: Some(feed.next_poll_after(polled_at)),
+            last_error: None,
+            ..feed.state().clone()
+        };
+
+        let (status, title, new_observations) = match self.fetch_feed(ingester, feed).await {
+            Ok(PolledFeed::NotModified) => (FeedPollStatus::NotModified, None, 0),
+            Ok(PolledFeed::Fetched {
+                title,
+                etag,
+                last_modified,
+                new_observations,
+            }) => {
+                state.etag = etag;
+                state.last_modified = last_modified;
+                (FeedPollStatus::Fetched, title, new_observations)
+            }
+            Err(err) => {
+                state.last_error = Some(err.to_string());
+                (FeedPollStatus::Failed, None, 0)
+            }
+        };
+
+        self.store
+            .record_feed_poll(
+                feed.id(),
+                title.as_deref(),
+                status,
+                new_observations,
+                &state,
+            )
+            .await?;
+
+        Ok(FeedPollReport {
+            feed_id: feed.id(),
+            url: feed.url().to_string(),
+            status,
+            new_observations,
+            error: state.last_error,
+        })
+    }
+
+    async fn fetch_feed(&self, ingester: &FeedIngester, feed: &Feed) -> Result<PolledFeed> {
+        let state = feed.state();
+        let outcome = ingester
+            .fetch_conditional(
+                feed.url(),
+                state.etag.as_deref(),
+                state.last_modified.as_deref(),
+            )
+            .await?;
+
+        let FetchOutcome::Fetched {
+            body,
+            etag,
+            last_modified,
+        } = outcome
+        else {
+            return Ok(PolledFeed::NotModified);
+        };
+
+        let parsed = parse_feed(&body, Some(feed.url()))?;
+        let mut new_observations = 0usize;
+        for observation in &parsed.observations {
+            let (_, inserted) = self.store.upsert_observation(observation).await?;
+            if inserted {
+                new_observations += 1;
+            }
+        }
+
+        Ok(PolledFeed::Fetched {
+            title: parsed.title,
+            etag,
+            last_modified,
+            new_observations,
+        })
     }
 
     pub async fn get_observation(&self, id: ObservationId) -> Result<Option<Observation>> {
-        self.store.get_observation(id).await
+        Ok(self.store.get_observation(id).await?)
     }
 
     pub async fn chunk_observation(
         &self,
         id: ObservationId,
-        chunk_size: usize,
+        options: ChunkOptions,
     ) -> Result<Option<usize>> {
         let Some(observation) = self.store.get_observation(id).await? else {
             return Ok(None);
         };
 
-        let chunks = observation.chunk(chunk_size)?;
-        self.store.upsert_chunks(&chunks).await?;
+        let chunks = observation.chunk(options, self.tokenizer.as_ref())?;
+        self.store
+            .replace_chunks(id, &options, self.tokenizer.id(), &chunks)
+            .await?;
         Ok(Some(chunks.len()))
     }
 
+    /// Observations whose current chunks were produced with options other
+    /// than `options`, or counted with another tokenizer.
+    pub async fn stale_chunkings(&self, options: ChunkOptions) -> Result<Vec<ObservationId>> {
+        Ok(self
+            .store
+            .stale_chunkings(&options, self.tokenizer.id())
+            .await?)
+    }
+
+    /// Re-chunks every observation whose chunks are stale with respect to
+    /// `options`. Each observation's chunk set is replaced atomically.
+    pub async fn rechunk_stale(&self, options: ChunkOptions) -> Result<RechunkReport> {
+        let mut report = RechunkReport::default();
+
+        for id in self.stale_chunkings(options).await? {
+            if let Some(n) = self.chunk_observation(id, options).await? {
+                report.observations += 1;
+                report.chunks += n;
+            }
+        }
+
+        Ok(report)
+    }
+
     pub async fn list_chunks(&self, observation_id: ObservationId) -> Result<Vec<Chunk>> {
-        self.store.list_chunks(observation_id).await
+        Ok(self.store.list_chunks(observation_id).await?)
+    }
+
+    /// Embeds the query text and returns the chunks closest to it among
+    /// those embedded by the configured model.
+    pub async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        let embedder = self.embedder()?;
+        let vector = embedder.embed(&[&query.text]).await?.pop().ok_or(
+            embedding::EmbedError::CountMismatch {
+                expected: 1,
+                actual: 0,
+            },
+        )?;
+
+        Ok(self
+            .store
+            .search_similar(embedder.model_id(), &vector, query.limit, &query.filters)
+            .await?)
+    }
+
+    /// Full-text search over chunk text, which needs no embedding model.
+    pub async fn keyword_search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        Ok(self
+            .store
+            .keyword_search(&query.text, query.limit, &query.filters)
+            .await?)
+    }
+
+    /// Runs keyword and vector search together and fuses their rankings,
+    /// keeping each chunk's per-signal ranks, then reranks the best of them
+    /// if a reranker is set.
+    pub async fn hybrid_search(
+        &self,
+        query: &SearchQuery,
+        config: RetrieverConfig,
+    ) -> Result<Vec<RetrievedChunk>> {
+        Ok(self.retriever(config)?.retrieve(query).await?)
+    }
+
+    /// Answers the question in `query.text` from hybrid search results,
+    /// with citations of the chunks the answer is based on.
+    pub async fn ask(
+        &self,
+        query: &SearchQuery,
+        retriever: RetrieverConfig,
+        answer: AnswerConfig,
+    ) -> Result<Answer> {
+        let llm = self.llm.clone().ok_or(AppError::NoLlm)?;
+        let answerer = Answerer::new(self.retriever(retriever)?, llm).with_config(answer);
+        Ok(answerer.answer(query).await?)
+    }
+
+    fn retriever(&self, config: RetrieverConfig) -> Result<HybridRetriever> {
+        let embedder = self.embedder.clone().ok_or(AppError::NoEmbedder)?;
+        let mut retriever = HybridRetriever::new(self.store.clone(), embedder).with_config(config);
+        if let Some(reranker) = &self.reranker {
+            retriever = retriever.with_reranker(reranker.clone());
+        }
+        Ok(retriever)
     }
 }
@@ -1,8 +1,26 @@
 use anyhow::{Context, Result, anyhow};
-use app::App;
-use clap::{Parser, Subcommand};
-use domain::ids::ObservationId;
-use std::path::PathBuf;
+use app::{App, EmbedConfig, EmbedProgress};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use domain::{
+    chunk::Chunk,
+    chunker::{ChunkOptions, ChunkSize, ChunkStrategy},
+    ids::{FeedId, ObservationId},
+    observation::SourceKind,
+    search::{DateField, ObservationSummary, SearchFilters, SearchQuery},
+    tokenizer::BpeTokenizer,
+};
+use embedding::{Embedder, HashingEmbedder, LocalEmbedder, OpenAiConfig, OpenAiEmbedder};
+use ingest::FeedSource;
+use rag::{
+    AnswerConfig, CrossEncoder, HttpReranker, HttpRerankerConfig, LlmClient, OpenAiChat,
+    OpenAiChatConfig, RerankApi, Reranker, RetrieverConfig, SignalScore,
+};
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 #[derive(Debug, Parser)]
 #[command(name = "crabtrap", version, about)]
@@ -10,6 +28,20 @@ struct Cli {
     #[arg(long, env = "DATABASE_URL")]
     database_url: String,
 
+    /// tiktoken BPE rank file (e.g. cl100k_base.tiktoken) used to count
+    /// chunk tokens; a fast heuristic is used when omitted.
+    #[arg(long, env = "CRABTRAP_TOKENIZER")]
+    tokenizer: Option<PathBuf>,
+
+    #[command(flatten)]
+    embedder: EmbedderArgs,
+
+    #[command(flatten)]
+    llm: LlmArgs,
+
+    #[command(flatten)]
+    reranker: RerankerArgs,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -32,6 +64,23 @@ enum Command {
         source_url: Option<String>,
     },
 
+    IngestFeed {
+        source: FeedSource,
+    },
+
+    IngestUrl {
+        url: String,
+    },
+
+    IngestPdf {
+        file: PathBuf,
+    },
+
+    Feed {
+        #[command(subcommand)]
+        command: FeedCommand,
+    },
+
     GetObservation {
         id: ObservationId,
     },
@@ -39,13 +88,531 @@ enum Command {
     Chunk {
         observation_id: ObservationId,
 
-        #[arg(long, default_value_t = 1000)]
-        chunk_size: usize,
+        #[command(flatten)]
+        chunking: ChunkArgs,
+    },
+
+    /// Re-chunk every observation whose chunks were made with other options
+    /// or, for token sizes, another tokenizer. Re-chunked observations get
+    /// new chunk ids and lose their embeddings, even where the text is
+    /// unchanged, so run `embed` afterwards.
+    Rechunk {
+        #[command(flatten)]
+        chunking: ChunkArgs,
+
+        /// Only list the observations that would be re-chunked.
+        #[arg(long)]
+        dry_run: bool,
     },
 
     ListChunks {
         observation_id: ObservationId,
     },
+
+    /// Embed every chunk that has no embedding from the configured model.
+    Embed {
+        /// Chunks per embedding request; the model's batch limit by default.
+        #[arg(long)]
+        batch_size: Option<usize>,
+
+        /// Batches embedded at the same time.
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+
+        /// Times a batch that failed with a transient provider or database
+        /// error is retried before the run stops.
+        #[arg(long, default_value_t = 3)]
+        batch_retries: u32,
+
+        /// Keep running, embedding new chunks every N seconds.
+        #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+        watch: Option<u64>,
+    },
+
+    /// Find the chunks that best match a query.
+    Search {
+        query: String,
+
+        /// Number of chunks to return.
+        #[arg(short, long, default_value_t = 10)]
+        k: usize,
+
+        #[arg(long, value_enum, default_value_t = SearchMode::Vector)]
+        mode: SearchMode,
+
+        /// Weight of the keyword ranking in hybrid mode.
+        #[arg(long, default_value_t = 1.0)]
+        keyword_weight: f32,
+
+        /// Weight of the vector ranking in hybrid mode.
+        #[arg(long, default_value_t = 1.0)]
+        vector_weight: f32,
+
+        /// Fused results reranked in hybrid mode when a reranker is set.
+        #[arg(long, default_value_t = 50)]
+        rerank_candidates: usize,
+
+        /// Relevance versus variety of hybrid results, from 0 to 1; 1 ranks
+        /// by relevance alone.
+        #[arg(long, default_value_t = 1.0)]
+        mmr_lambda: f32,
+
+        /// Most hybrid results from one observation.
+        #[arg(long)]
+        max_per_observation: Option<usize>,
+
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+
+    /// Answer a question from the stored observations, citing the chunks
+    /// the answer is based on.
+    Ask {
+        question: String,
+
+        /// Number of chunks to retrieve.
+        #[arg(short, long, default_value_t = 8)]
+        k: usize,
+
+        /// Most chunk tokens to put in the prompt.
+        #[arg(long, default_value_t = 3000)]
+        context_tokens: usize,
+
+        /// Retrieved chunks reranked when a reranker is set.
+        #[arg(long, default_value_t = 50)]
+        rerank_candidates: usize,
+
+        /// Relevance versus variety of the chunks, from 0 to 1; 1 ranks by
+        /// relevance alone.
+        #[arg(long, default_value_t = 1.0)]
+        mmr_lambda: f32,
+
+        /// Most chunks from one observation in the prompt.
+        #[arg(long)]
+        max_per_observation: Option<usize>,
+
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+}
+
+#[derive(Debug, Args)]
+struct LlmArgs {
+    /// API root of the chat model server (OpenAI-compatible), including
+    /// the version prefix.
+    #[arg(long, env = "CRABTRAP_LLM_URL")]
+    llm_url: Option<String>,
+
+    #[arg(long, env = "CRABTRAP_LLM_MODEL")]
+    llm_model: Option<String>,
+
+    #[arg(long, env = "CRABTRAP_LLM_API_KEY", hide_env_values = true)]
+    llm_api_key: Option<String>,
+}
+
+impl LlmArgs {
+    fn build(&self) -> Result<Arc<dyn LlmClient>> {
+        let url = self
+            .llm_url
+            .clone()
+            .context("no chat model configured; pass --llm-url")?;
+        let model = self
+            .llm_model
+            .clone()
+            .context("--llm-url needs --llm-model")?;
+        let mut config = OpenAiChatConfig::new(url, model);
+        if let Some(api_key) = &self.llm_api_key {
+            config = config.with_api_key(api_key);
+        }
+        Ok(Arc::new(OpenAiChat::new(config)?))
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RerankerKind {
+    /// A server with a `/rerank` endpoint.
+    Http,
+    /// A BERT cross-encoder run on the CPU.
+    Local,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RerankApiArg {
+    Tei,
+    Cohere,
+}
+
+impl From<RerankApiArg> for RerankApi {
+    fn from(api: RerankApiArg) -> Self {
+        match api {
+            RerankApiArg::Tei => Self::Tei,
+            RerankApiArg::Cohere => Self::Cohere,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct RerankerArgs {
+    /// Model that reorders hybrid search results; none by default.
+    #[arg(long, env = "CRABTRAP_RERANKER")]
+    reranker: Option<RerankerKind>,
+
+    /// Root of the rerank server, which `/rerank` is appended to.
+    #[arg(long, env = "CRABTRAP_RERANK_URL")]
+    rerank_url: Option<String>,
+
+    /// Request format of the rerank server.
+    #[arg(long, value_enum, env = "CRABTRAP_RERANK_API", default_value_t = RerankApiArg::Tei)]
+    rerank_api: RerankApiArg,
+
+    #[arg(long, env = "CRABTRAP_RERANK_MODEL")]
+    rerank_model: Option<String>,
+
+    #[arg(long, env = "CRABTRAP_RERANK_API_KEY", hide_env_values = true)]
+    rerank_api_key: Option<String>,
+
+    /// Directory of the local cross-encoder (config.json, tokenizer.json
+    /// and model.safetensors).
+    #[arg(long, env = "CRABTRAP_RERANK_MODEL_DIR")]
+    rerank_model_dir: Option<PathBuf>,
+}
+
+impl RerankerArgs {
+    fn build(&self) -> Result<Option<Arc<dyn Reranker>>> {
+        let Some(kind) = self.reranker else {
+            return Ok(None);
+        };
+
+        Ok(Some(match kind {
+            RerankerKind::Http => {
+                let url = self
+                    .rerank_url
+                    .clone()
+                    .context("--reranker http needs --rerank-url")?;
+                let mut config = HttpRerankerConfig::new(url, self.rerank_api.into());
+                if let Some(model) = &self.rerank_model {
+                    config = config.with_model(model);
+                }
+                if let Some(api_key) = &self.rerank_api_key {
+                    config = config.with_api_key(api_key);
+                }
+                Arc::new(HttpReranker::new(config)?)
+            }
+            RerankerKind::Local => {
+                let dir = self
+                    .rerank_model_dir
+                    .as_ref()
+                    .context("--reranker local needs --rerank-model-dir")?;
+                Arc::new(
+                    CrossEncoder::load(dir)
+                        .with_context(|| format!("loading model {}", dir.display()))?,
+                )
+            }
+        }))
+    }
+
+    /// Adds the configured reranker, if any, to `app`.
+    fn apply(&self, app: App) -> Result<App> {
+        Ok(match self.build()? {
+            Some(reranker) => app.with_reranker(reranker),
+            None => app,
+        })
+    }
+}
+
+#[derive(Debug, Args)]
+struct FilterArgs {
+    /// Only observations of this kind (rss, web, pdf, text, ...); repeat
+    /// for several.
+    #[arg(long = "kind", value_name = "KIND")]
+    kinds: Vec<SourceKind>,
+
+    /// Only observations dated on or after this day (YYYY-MM-DD) or time
+    /// (RFC 3339).
+    #[arg(long, value_parser = parse_since)]
+    since: Option<DateTime<Utc>>,
+
+    /// Only observations dated on or before this day, or before this time.
+    #[arg(long, value_parser = parse_until)]
+    until: Option<DateTime<Utc>>,
+
+    /// Which date --since and --until apply to.
+    #[arg(long, value_enum, default_value_t = DateFieldArg::Published)]
+    date_field: DateFieldArg,
+
+    /// Only sources on this host or its subdomains.
+    #[arg(long)]
+    domain: Option<String>,
+
+    /// Only observations whose title contains this text, ignoring case.
+    #[arg(long)]
+    title: Option<String>,
+}
+
+impl FilterArgs {
+    fn filters(&self) -> SearchFilters {
+        let mut filters = SearchFilters {
+            source_kinds: self.kinds.clone(),
+            date_field: match self.date_field {
+                DateFieldArg::Published => DateField::Published,
+                DateFieldArg::Created => DateField::Created,
+            },
+            since: self.since,
+            until: self.until,
+            ..SearchFilters::default()
+        };
+        if let Some(domain) = &self.domain {
+            filters = filters.with_domain(domain);
+        }
+        if let Some(title) = &self.title {
+            filters = filters.with_title_contains(title);
+        }
+        filters
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum DateFieldArg {
+    /// The source's publication date, or the ingestion date without one.
+    Published,
+    /// When the observation was ingested.
+    Created,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SearchMode {
+    /// Nearest neighbours of the query's embedding.
+    Vector,
+    /// Full-text search on words, which needs no embedding model.
+    Keyword,
+    /// Keyword and vector search fused by reciprocal rank.
+    Hybrid,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum EmbedderKind {
+    /// A server speaking the OpenAI `/v1/embeddings` protocol.
+    Openai,
+    /// A sentence-transformers model run on the CPU.
+    Local,
+    /// Feature hashing of character n-grams: deterministic and needs no
+    /// model, for trying things out and tests.
+    Hashing,
+}
+
+#[derive(Debug, Args)]
+struct EmbedderArgs {
+    /// Embedding model used to embed chunks and queries.
+    #[arg(long, env = "CRABTRAP_EMBEDDER")]
+    embedder: Option<EmbedderKind>,
+
+    /// API root of the embedding server, including the version prefix.
+    #[arg(long, env = "CRABTRAP_EMBEDDING_URL")]
+    embedding_url: Option<String>,
+
+    #[arg(long, env = "CRABTRAP_EMBEDDING_MODEL")]
+    embedding_model: Option<String>,
+
+    /// Length of the vectors the embedding server returns, or of the
+    /// hashing embedder's vectors.
+    #[arg(long, env = "CRABTRAP_EMBEDDING_DIMENSION")]
+    embedding_dimension: Option<usize>,
+
+    #[arg(long, env = "CRABTRAP_EMBEDDING_API_KEY", hide_env_values = true)]
+    embedding_api_key: Option<String>,
+
+    /// Retries of an embedding request that failed with a retryable error.
+    #[arg(long, env = "CRABTRAP_EMBEDDING_MAX_RETRIES", default_value_t = 3)]
+    embedding_max_retries: u32,
+
+    /// Directory of the local model (config.json, tokenizer.json and
+    /// model.safetensors).
+    #[arg(long, env = "CRABTRAP_MODEL_DIR")]
+    model_dir: Option<PathBuf>,
+}
+
+impl EmbedderArgs {
+    fn build(&self) -> Result<Arc<dyn Embedder>> {
+        let Some(kind) = self.embedder else {
+            return Err(anyhow!("no embedder configured; pass --embedder"));
+        };
+
+        Ok(match kind {
+            EmbedderKind::Openai => {
+                let url = self
+                    .embedding_url
+                    .clone()
+                    .context("--embedder openai needs --embedding-url")?;
+                let model = self
+                    .embedding_model
+                    .clone()
+                    .context("--embedder openai needs --embedding-model")?;
+                let dimension = self
+                    .embedding_dimension
+                    .context("--embedder openai needs --embedding-dimension")?;
+                let mut config = OpenAiConfig::new(url, model, dimension)
+                    .with_max_retries(self.embedding_max_retries);
+                if let Some(api_key) = &self.embedding_api_key {
+                    config = config.with_api_key(api_key);
+                }
+                Arc::new(OpenAiEmbedder::new(config)?)
+            }
+            EmbedderKind::Local => {
+                let dir = self
+                    .model_dir
+                    .as_ref()
+                    .context("--embedder local needs --model-dir")?;
+                let mut embedder = LocalEmbedder::load(dir)
+                    .with_context(|| format!("loading model {}", dir.display()))?;
+                if let Some(model) = &self.embedding_model {
+                    embedder = embedder.with_model_id(model);
+                }
+                Arc::new(embedder)
+            }
+            EmbedderKind::Hashing => {
+                let mut embedder = self
+                    .embedding_dimension
+                    .map_or_else(HashingEmbedder::default, HashingEmbedder::new);
+                if let Some(model) = &self.embedding_model {
+                    embedder = embedder.with_model_id(model);
+                }
+                Arc::new(embedder)
+            }
+        })
+    }
+}
+
+#[derive(Debug, Args)]
+struct ChunkArgs {
+    /// Where chunks may be cut: "fixed", "recursive" (paragraphs, then
+    /// sentences, then words) or "markdown" (sections and blocks).
+    #[arg(long, default_value_t = ChunkStrategy::Fixed)]
+    strategy: ChunkStrategy,
+
+    #[arg(long, default_value_t = 1000, conflicts_with = "max_tokens")]
+    chunk_size: usize,
+
+    /// Budget chunks in tokens instead of bytes.
+    #[arg(long)]
+    max_tokens: Option<usize>,
+
+    /// How much of each chunk to repeat at the start of the next, in
+    /// bytes or, with --max-tokens, tokens.
+    #[arg(long, default_value_t = 0)]
+    overlap: usize,
+}
+
+impl ChunkArgs {
+    fn options(&self) -> ChunkOptions {
+        let size = self
+            .max_tokens
+            .map_or(ChunkSize::Bytes(self.chunk_size), ChunkSize::Tokens);
+        ChunkOptions::new(size)
+            .with_strategy(self.strategy)
+            .with_overlap(self.overlap)
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum FeedCommand {
+    Add {
+        url: String,
+
+        #[arg(long, default_value_t = 3600)]
+        interval_secs: u64,
+    },
+
+    List,
+
+    Remove {
+        id: FeedId,
+    },
+
+    Poll {
+        /// Poll every feed, not only those that are due.
+        #[arg(long)]
+        all: bool,
+
+        /// Keep running, polling due feeds every N seconds. Failed passes
+        /// are reported and retried on the next one.
+        #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+        watch: Option<u64>,
+    },
+}
+
+fn parse_since(value: &str) -> std::result::Result<DateTime<Utc>, String> {
+    parse_time(value, false)
+}
+
+fn parse_until(value: &str) -> std::result::Result<DateTime<Utc>, String> {
+    parse_time(value, true)
+}
+
+/// Parses an RFC 3339 time or a `YYYY-MM-DD` day. A day is the midnight
+/// starting it, or with `end_of_day` the midnight ending it.
+fn parse_time(value: &str, end_of_day: bool) -> std::result::Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let day = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("expected YYYY-MM-DD or an RFC 3339 time, got {value:?}"))?;
+    let day = if end_of_day {
+        day.succ_opt().ok_or("date out of range")?
+    } else {
+        day
+    };
+    Ok(day.and_time(NaiveTime::MIN).and_utc())
+}
+
+/// How much of a chunk's text search results show.
+const SNIPPET_CHARS: usize = 200;
+
+/// The first `max_chars` characters of `text` on a single line.
+fn snippet(text: &str, max_chars: usize) -> String {
+    let flat = one_line(text);
+    match flat.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &flat[..end]),
+        None => flat,
+    }
+}
+
+fn print_result(
+    rank: usize,
+    scores: &str,
+    chunk: &Chunk,
+    observation: &ObservationSummary,
+    highlighted: Option<&str>,
+) {
+    println!(
+        "{rank}. {scores} chunk={} observation={} start={} end={}",
+        chunk.id(),
+        observation.id,
+        chunk.start_offset(),
+        chunk.end_offset()
+    );
+    if let Some(title) = &observation.title {
+        println!("   title: {title}");
+    }
+    if let Some(url) = &observation.source_url {
+        println!("   url: {url}");
+    }
+    match highlighted {
+        Some(highlighted) => println!("   {}", one_line(highlighted)),
+        None => println!("   {}", snippet(chunk.text(), SNIPPET_CHARS)),
+    }
+}
+
+/// A chunk's rank and score in one hybrid search signal, or `-` if that
+/// signal did not find it.
+fn signal(score: Option<SignalScore>) -> String {
+    score.map_or_else(
+        || "-".to_string(),
+        |s| format!("#{} ({:.4})", s.rank, s.score),
+    )
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
 fn resolve_content(content: Option<String>, file: Option<PathBuf>) -> Result<String> {
@@ -62,7 +629,12 @@ fn resolve_content(content: Option<String>, file: Option<PathBuf>) -> Result<Str
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    let app = App::connect(&cli.database_url).await?;
+    let mut app = App::connect(&cli.database_url).await?;
+    if let Some(path) = &cli.tokenizer {
+        let tokenizer = BpeTokenizer::from_file(path)
+            .with_context(|| format!("loading tokenizer {}", path.display()))?;
+        app = app.with_tokenizer(Arc::new(tokenizer));
+    }
 
     match cli.command {
         Command::Migrate => {
@@ -85,6 +657,34 @@ async fn main() -> Result<()> {
             }
         }
 
+        Command::IngestFeed { source } => {
+            let report = app.ingest_feed(&source).await?;
+            println!(
+                "ok: {} inserted, {} existing, {} skipped from {source}",
+                report.inserted, report.existing, report.skipped
+            );
+        }
+
+        Command::IngestUrl { url } => {
+            let (id, inserted) = app.ingest_url(&url).await?;
+            if inserted {
+                println!("ok: inserted observation {id}");
+            } else {
+                println!("ok: existing observation {id}");
+            }
+        }
+
+        Command::IngestPdf { file } => {
+            let (id, inserted) = app.ingest_pdf(&file).await?;
+            if inserted {
+                println!("ok: inserted observation {id}");
+            } else {
+                println!("ok: existing observation {id}");
+            }
+        }
+
+        Command::Feed { command } => run_feed_command(&app, command).await?,
+
         Command::GetObservation { id } => {
             let Some(obs) = app.get_observation(id).await? else {
                 println!("not found: observation {id}");
@@ -109,20 +709,74 @@ async fn main() -> Result<()> {
 
         Command::Chunk {
             observation_id,
-            chunk_size,
+            chunking,
         } => {
-            let Some(n) = app.chunk_observation(observation_id, chunk_size).await? else {
+            let Some(n) = app
+                .chunk_observation(observation_id, chunking.options())
+                .await?
+            else {
                 println!("not found: observation {observation_id}");
                 return Ok(());
             };
-            println!("ok: upserted {n} chunks");
+            println!("ok: stored {n} chunks");
+        }
+
+        Command::Rechunk { chunking, dry_run } => {
+            let options = chunking.options();
+            if dry_run {
+                for id in app.stale_chunkings(options).await? {
+                    println!("{id}");
+                }
+            } else {
+                let report = app.rechunk_stale(options).await?;
+                println!(
+                    "ok: re-chunked {} observations into {} chunks",
+                    report.observations, report.chunks
+                );
+            }
+        }
+
+        Command::Embed {
+            batch_size,
+            concurrency,
+            batch_retries,
+            watch,
+        } => {
+            let app = app.with_embedder(cli.embedder.build()?);
+            let mut config = EmbedConfig::default()
+                .with_concurrency(concurrency)
+                .with_max_retries(batch_retries);
+            if let Some(batch_size) = batch_size {
+                config = config.with_batch_size(batch_size);
+            }
+            run_embed(&app, config, watch).await?;
         }
 
         Command::ListChunks { observation_id } => {
+            let page_map = app
+                .get_observation(observation_id)
+                .await?
+                .and_then(|obs| obs.page_map().cloned());
             let chunks = app.list_chunks(observation_id).await?;
             for c in chunks {
+                let pages = page_map
+                    .as_ref()
+                    .and_then(|map| map.pages_for_range(c.start_offset(), c.end_offset()))
+                    .map(|(first, last)| {
+                        if first == last {
+                            format!(" pages={first}")
+                        } else {
+                            format!(" pages={first}-{last}")
+                        }
+                    })
+                    .unwrap_or_default();
+                let path = if c.heading_path().is_empty() {
+                    String::new()
+                } else {
+                    format!(" path={:?}", c.heading_path().join(" > "))
+                };
                 println!(
-                    "{} idx={} bytes={} start={} end={} tokens={}",
+                    "{} idx={} bytes={} start={} end={} tokens={}{pages}{path}",
                     c.id(),
                     c.index(),
                     c.text().len(),
@@ -132,6 +786,257 @@ async fn main() -> Result<()> {
                 );
             }
         }
+
+        Command::Search {
+            query,
+            k,
+            mode,
+            keyword_weight,
+            vector_weight,
+            rerank_candidates,
+            mmr_lambda,
+            max_per_observation,
+            filters,
+        } => {
+            let query = SearchQuery::new(query, k).with_filters(filters.filters());
+            let hits = match mode {
+                SearchMode::Vector => {
+                    let app = app.with_embedder(cli.embedder.build()?);
+                    app.search(&query).await?
+                }
+                SearchMode::Keyword => app.keyword_search(&query).await?,
+                SearchMode::Hybrid => {
+                    let app = cli
+                        .reranker
+                        .apply(app.with_embedder(cli.embedder.build()?))?;
+                    let mut config = RetrieverConfig::default()
+                        .with_weights(keyword_weight, vector_weight)
+                        .with_rerank_candidates(rerank_candidates)
+                        .with_mmr_lambda(mmr_lambda);
+                    if let Some(max) = max_per_observation {
+                        config = config.with_max_per_observation(max);
+                    }
+                    let results = app.hybrid_search(&query, config).await?;
+                    if results.is_empty() {
+                        println!("no results");
+                    }
+                    for (rank, r) in results.iter().enumerate() {
+                        let mut scores = format!(
+                            "score={:.4} keyword={} vector={}",
+                            r.score,
+                            signal(r.keyword),
+                            signal(r.vector)
+                        );
+                        if let Some(rerank) = r.rerank_score {
+                            scores.push_str(&format!(" rerank={rerank:.4}"));
+                        }
+                        print_result(
+                            rank + 1,
+                            &scores,
+                            &r.chunk,
+                            &r.observation,
+                            r.snippet.as_deref(),
+                        );
+                    }
+                    return Ok(());
+                }
+            };
+            if hits.is_empty() {
+                println!("no results");
+            }
+            for (rank, hit) in hits.iter().enumerate() {
+                let scores = format!("score={:.4}", hit.score);
+                print_result(
+                    rank + 1,
+                    &scores,
+                    &hit.chunk,
+                    &hit.observation,
+                    hit.snippet.as_deref(),
+                );
+            }
+        }
+
+        Command::Ask {
+            question,
+            k,
+            context_tokens,
+            rerank_candidates,
+            mmr_lambda,
+            max_per_observation,
+            filters,
+        } => {
+            let app = cli.reranker.apply(
+                app.with_embedder(cli.embedder.build()?)
+                    .with_llm(cli.llm.build()?),
+            )?;
+            let query = SearchQuery::new(question, k).with_filters(filters.filters());
+            let mut config = RetrieverConfig::default()
+                .with_rerank_candidates(rerank_candidates)
+                .with_mmr_lambda(mmr_lambda);
+            if let Some(max) = max_per_observation {
+                config = config.with_max_per_observation(max);
+            }
+            let answer = app
+                .ask(
+                    &query,
+                    config,
+                    AnswerConfig::default().with_context_tokens(context_tokens),
+                )
+                .await?;
+
+            println!("{}", answer.text.trim());
+            if !answer.citations.is_empty() {
+                println!("\nSources:");
+            }
+            for citation in &answer.citations {
+                println!(
+                    "[{}] chunk={} observation={} start={} end={}",
+                    citation.number,
+                    citation.chunk_id,
+                    citation.observation_id,
+                    citation.start_offset,
+                    citation.end_offset
+                );
+                if let Some(title) = &citation.title {
+                    println!("    title: {title}");
+                }
+                if let Some(url) = &citation.source_url {
+                    println!("    url: {url}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// How often `embed` reports progress while it runs.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Embeds pending chunks, once or every `watch` seconds until interrupted.
+/// In watch mode a failed pass is reported and retried on the next one.
+async fn run_embed(app: &App, config: EmbedConfig, watch: Option<u64>) -> Result<()> {
+    loop {
+        let mut last_report = Instant::now();
+        let pass = app.embed_pending(config, |progress| {
+            if last_report.elapsed() >= PROGRESS_INTERVAL {
+                last_report = Instant::now();
+                println!("{}", progress_line("progress", progress));
+            }
+        });
+
+        let outcome = tokio::select! {
+            outcome = pass => outcome,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        match outcome {
+            Ok(progress) => println!("{}", progress_line("ok", &progress)),
+            Err(err) if watch.is_some() => println!("error: {err}"),
+            Err(err) => return Err(err.into()),
+        }
+
+        let Some(secs) = watch else {
+            break;
+        };
+
+        tokio::select! {
+            () = tokio::time::sleep(Duration::from_secs(secs)) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    Ok(())
+}
+
+fn progress_line(label: &str, progress: &EmbedProgress) -> String {
+    let hit_rate = progress
+        .cache_hit_rate()
+        .map_or_else(|| "-".to_string(), |rate| format!("{:.1}%", rate * 100.0));
+    format!(
+        "{label}: done={} pending={} failed={} cache_hits={} cache_misses={} hit_rate={hit_rate}",
+        progress.done,
+        progress.pending,
+        progress.failed,
+        progress.cache_hits,
+        progress.cache_misses
+    )
+}
+
+async fn run_feed_command(app: &App, command: FeedCommand) -> Result<()> {
+    match command {
+        FeedCommand::Add { url, interval_secs } => {
+            let (id, inserted) = app
+                .add_feed(&url, Duration::from_secs(interval_secs))
+                .await?;
+            if inserted {
+                println!("ok: added feed {id}");
+            } else {
+                println!("ok: existing feed {id}");
+            }
+        }
+
+        FeedCommand::List => {
+            for feed in app.list_feeds().await? {
+                let state = feed.state();
+                println!(
+                    "{} interval={}s last_polled={} next_poll={} url={}",
+                    feed.id(),
+                    feed.poll_interval().as_secs(),
+                    state
+                        .last_polled_at
+                        .map_or_else(|| "never".to_string(), |t| t.to_rfc3339()),
+                    state
+                        .next_poll_at
+                        .map_or_else(|| "now".to_string(), |t| t.to_rfc3339()),
+                    feed.url()
+                );
+                if let Some(title) = feed.title() {
+                    println!("  title: {title}");
+                }
+                if let Some(error) = &state.last_error {
+                    println!("  last_error: {error}");
+                }
+            }
+        }
+
+        FeedCommand::Remove { id } => {
+            if app.remove_feed(id).await? {
+                println!("ok: removed feed {id}");
+            } else {
+                println!("not found: feed {id}");
+            }
+        }
+
+        FeedCommand::Poll { all, watch } => loop {
+            let reports = match app.poll_feeds(all).await {
+                Ok(reports) => reports,
+                Err(err) if watch.is_some() => {
+                    println!("error: {err}");
+                    Vec::new()
+                }
+                Err(err) => return Err(err.into()),
+            };
+            for report in reports {
+                match report.error {
+                    Some(error) => println!("error: {} {error}", report.url),
+                    None => println!(
+                        "ok: {} {} new={}",
+                        report.url,
+                        report.status.as_str(),
+                        report.new_observations
+                    ),
+                }
+            }
+
+            let Some(secs) = watch else {
+                break;
+            };
+
+            tokio::select! {
+                () = tokio::time::sleep(Duration::from_secs(secs)) => {}
+                _ = tokio::signal::ctrl_c() => break,
+            }
+        },
     }
 
     Ok(())
@@ -1,6 +1,7 @@
 use crate::{
     ids::{ChunkId, ObservationId},
     observation::Observation,
+    tokenizer::Tokenizer,
 };
 
 // mod private {
@@ -37,6 +38,7 @@ use crate::{
 //     end_offset: usize, // embedding_data: Option<Embedding>
 // }
 
+#[derive(Debug, Clone)]
 pub struct Chunk {
     pub(crate) id: ChunkId,
     pub(crate) observation_id: ObservationId,
@@ -45,6 +47,7 @@ pub struct Chunk {
     pub(crate) start_offset: usize,
     pub(crate) end_offset: usize,
     pub(crate) token_estimate: u32,
+    pub(crate) heading_path: Vec<String>,
 }
 
 impl Chunk {
@@ -66,6 +69,7 @@ impl Chunk {
             start_offset,
             end_offset,
             token_estimate,
+            heading_path: Vec::new(),
         }
     }
 
@@ -75,18 +79,30 @@ impl Chunk {
         text: impl Into<String>,
         start: usize,
         end: usize,
+        tokenizer: &dyn Tokenizer,
     ) -> Self {
+        let text = text.into();
+        let token_estimate = u32::try_from(tokenizer.count_tokens(&text)).unwrap_or(u32::MAX);
+
         Self {
             id: ChunkId::new(),
             observation_id: observation.id(),
             index,
-            text: text.into(),
+            text,
             start_offset: start,
             end_offset: end,
-            token_estimate: 1,
+            token_estimate,
+            heading_path: Vec::new(),
         }
     }
 
+    /// Sets the headings enclosing the chunk, outermost first.
+    #[must_use]
+    pub fn with_heading_path(mut self, heading_path: Vec<String>) -> Self {
+        self.heading_path = heading_path;
+        self
+    }
+
     #[must_use]
     pub const fn id(&self) -> ChunkId {
         self.id
@@ -121,4 +137,11 @@ impl Chunk {
     pub const fn token_estimate(&self) -> u32 {
         self.token_estimate
     }
+
+    /// The headings enclosing the chunk in its source document, outermost
+    /// first. Empty for chunks of unstructured text.
+    #[must_use]
+    pub fn heading_path(&self) -> &[String] {
+        &self.heading_path
+    }
 }
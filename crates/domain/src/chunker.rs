@@ -0,0 +1,490 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    chunk::Chunk,
+    error::{Result, ValidationError},
+    observation::Observation,
+    tokenizer::{Tokenizer, pre_tokenize},
+};
+
+mod markdown;
+mod recursive;
+
+/// The budget each chunk must fit in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkSize {
+    /// At most this many bytes of UTF-8 text.
+    Bytes(usize),
+    /// At most this many tokens, as counted by the chunker's tokenizer.
+    Tokens(usize),
+}
+
+impl ChunkSize {
+    #[must_use]
+    pub const fn limit(&self) -> usize {
+        match self {
+            Self::Bytes(n) | Self::Tokens(n) => *n,
+        }
+    }
+
+    /// The unit the limit is counted in: `"bytes"` or `"tokens"`.
+    #[must_use]
+    pub const fn unit(&self) -> &str {
+        match self {
+            Self::Bytes(_) => "bytes",
+            Self::Tokens(_) => "tokens",
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.limit() == 0 {
+            return Err(ValidationError::InvalidChunkSize.into());
+        }
+        Ok(())
+    }
+}
+
+/// Where chunk boundaries are allowed to fall.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkStrategy {
+    /// Cut wherever the budget runs out.
+    #[default]
+    Fixed,
+    /// Cut at paragraph breaks, then sentence ends, then between words,
+    /// falling back to char boundaries only for oversized words.
+    Recursive,
+    /// Cut between Markdown sections and blocks, never inside a fenced code
+    /// block, and record each chunk's heading path.
+    Markdown,
+}
+
+impl ChunkStrategy {
+    pub const fn as_str(&self) -> &str {
+        match self {
+            Self::Fixed => "fixed",
+            Self::Recursive => "recursive",
+            Self::Markdown => "markdown",
+        }
+    }
+}
+
+impl FromStr for ChunkStrategy {
+    type Err = ValidationError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "fixed" => Ok(Self::Fixed),
+            "recursive" => Ok(Self::Recursive),
+            "markdown" => Ok(Self::Markdown),
+            _ => Err(ValidationError::UnknownChunkStrategy(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for ChunkStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How an observation is split into chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkOptions {
+    pub strategy: ChunkStrategy,
+    pub size: ChunkSize,
+    /// How much of the end of each chunk is repeated at the start of the
+    /// next, in the same unit as `size`.
+    pub overlap: usize,
+}
+
+impl ChunkOptions {
+    #[must_use]
+    pub const fn new(size: ChunkSize) -> Self {
+        Self {
+            strategy: ChunkStrategy::Fixed,
+            size,
+            overlap: 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_strategy(mut self, strategy: ChunkStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        self.size.validate()?;
+        if self.overlap >= self.size.limit() {
+            return Err(ValidationError::InvalidChunkOverlap.into());
+        }
+        Ok(())
+    }
+}
+
+impl From<ChunkSize> for ChunkOptions {
+    fn from(size: ChunkSize) -> Self {
+        Self::new(size)
+    }
+}
+
+/// Measures text in the unit of a [`ChunkSize`].
+#[derive(Clone, Copy)]
+pub(crate) struct Measure<'a> {
+    size: ChunkSize,
+    tokenizer: &'a dyn Tokenizer,
+}
+
+impl<'a> Measure<'a> {
+    pub(crate) fn new(size: ChunkSize, tokenizer: &'a dyn Tokenizer) -> Self {
+        Self { size, tokenizer }
+    }
+
+    pub(crate) const fn limit(&self) -> usize {
+        self.size.limit()
+    }
+
+    /// Size of `text` in the budget's unit.
+    pub(crate) fn size_of(&self, text: &str) -> usize {
+        match self.size {
+            ChunkSize::Bytes(_) => text.len(),
+            ChunkSize::Tokens(_) => self.tokenizer.count_tokens(text),
+        }
+    }
+
+    /// Byte length of the longest prefix of `text` that fits in `budget`,
+    /// ending on a char boundary. May be zero if the first char alone is
+    /// over budget.
+    ///
+    /// Token counts are summed over [`pre_tokenize`] pieces, which is exact
+    /// for tokenizers that never merge across piece boundaries (BPE and the
+    /// heuristic both qualify).
+    pub(crate) fn fit_prefix(&self, text: &str, budget: usize) -> usize {
+        match self.size {
+            ChunkSize::Bytes(_) => floor_char_boundary(text, budget.min(text.len())),
+            ChunkSize::Tokens(_) => {
+                let mut used = 0usize;
+                let mut end = 0usize;
+                for piece in pre_tokenize(text) {
+                    let cost = self.tokenizer.count_tokens(piece);
+                    if used + cost > budget {
+                        return end + self.fit_within_piece(piece, budget - used);
+                    }
+                    used += cost;
+                    end += piece.len();
+                }
+                end
+            }
+        }
+    }
+
+    /// Byte length of the longest suffix of `text` that fits in `budget`,
+    /// starting on a char boundary. Token-measured suffixes start on a
+    /// [`pre_tokenize`] piece boundary, so an overlap never opens mid-word.
+    pub(crate) fn fit_suffix(&self, text: &str, budget: usize) -> usize {
+        match self.size {
+            ChunkSize::Bytes(_) => {
+                text.len() - ceil_char_boundary(text, text.len().saturating_sub(budget))
+            }
+            ChunkSize::Tokens(_) => {
+                let pieces: Vec<&str> = pre_tokenize(text).collect();
+                let mut used = 0usize;
+                let mut len = 0usize;
+                for piece in pieces.iter().rev() {
+                    used += self.tokenizer.count_tokens(piece);
+                    if used > budget {
+                        break;
+                    }
+                    len += piece.len();
+                }
+                len
+            }
+        }
+    }
+
+    /// Longest char-boundary prefix of a single pre-token piece that fits in
+    /// `budget` tokens, for words too long to fit whole.
+    fn fit_within_piece(&self, piece: &str, budget: usize) -> usize {
+        let boundaries: Vec<usize> = piece.char_indices().map(|(i, _)| i).skip(1).collect();
+        let fits = |end: usize| self.tokenizer.count_tokens(&piece[..end]) <= budget;
+        let idx = boundaries.partition_point(|end| fits(*end));
+        if idx == 0 { 0 } else { boundaries[idx - 1] }
+    }
+}
+
+/// Largest char boundary in `text` at or below `index`.
+pub(crate) fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while index > 0 && !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Smallest char boundary in `text` at or above `index`.
+pub(crate) fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    while index < text.len() && !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+/// Smallest char boundary in `text` strictly after `index`.
+pub(crate) fn next_char_boundary(text: &str, index: usize) -> usize {
+    text[index..]
+        .chars()
+        .next()
+        .map_or(text.len(), |c| index + c.len_utf8())
+}
+
+/// Splits `observation` into chunks using the strategy in `options`.
+pub fn chunk(
+    observation: &Observation,
+    options: ChunkOptions,
+    tokenizer: &dyn Tokenizer,
+) -> Result<Vec<Chunk>> {
+    match options.strategy {
+        ChunkStrategy::Fixed => fixed_size_chunks(observation, options, tokenizer),
+        ChunkStrategy::Recursive => {
+            options.validate()?;
+            Ok(recursive::recursive_chunks(observation, options, tokenizer))
+        }
+        ChunkStrategy::Markdown => {
+            options.validate()?;
+            Ok(markdown::markdown_chunks(observation, options, tokenizer))
+        }
+    }
+}
+
+/// Numbers chunk ranges into chunks, leaving whitespace at the edges of
+/// each range out of the chunk and skipping ranges that are all whitespace.
+fn build_chunks(
+    observation: &Observation,
+    ranges: impl IntoIterator<Item = ((usize, usize), Vec<String>)>,
+    tokenizer: &dyn Tokenizer,
+) -> Vec<Chunk> {
+    let text = observation.content();
+
+    ranges
+        .into_iter()
+        .filter_map(|((start, end), heading_path)| {
+            let raw = &text[start..end];
+            let trimmed = raw.trim();
+            (!trimmed.is_empty()).then(|| {
+                let start = start + (raw.len() - raw.trim_start().len());
+                (start, start + trimmed.len(), heading_path)
+            })
+        })
+        .zip(0i32..)
+        .map(|((start, end, heading_path), index)| {
+            Chunk::new(observation, index, &text[start..end], start, end, tokenizer)
+                .with_heading_path(heading_path)
+        })
+        .collect()
+}
+
+/// Splits `observation` into a sliding window of chunks that each fit the
+/// size budget, cutting only on char boundaries. Each chunk after the first
+/// starts with up to `overlap` of the previous chunk's tail. A single char
+/// larger than the budget becomes a chunk of its own.
+pub fn fixed_size_chunks(
+    observation: &Observation,
+    options: ChunkOptions,
+    tokenizer: &dyn Tokenizer,
+) -> Result<Vec<Chunk>> {
+    options.validate()?;
+
+    let measure = Measure::new(options.size, tokenizer);
+    let text = observation.content();
+    let mut chunks = Vec::new();
+    let mut start = 0usize;
+    let mut index = 0i32;
+
+    while start < text.len() {
+        let mut end = start + measure.fit_prefix(&text[start..], measure.limit());
+        if end == start {
+            end = next_char_boundary(text, start);
+        }
+
+        chunks.push(Chunk::new(
+            observation,
+            index,
+            &text[start..end],
+            start,
+            end,
+            tokenizer,
+        ));
+
+        if end == text.len() {
+            break;
+        }
+        // Always move forward, even when the whole chunk would fit in the
+        // overlap (e.g. a lone oversized char).
+        let overlap = measure.fit_suffix(&text[start..end], options.overlap);
+        start = if end - overlap > start {
+            end - overlap
+        } else {
+            end
+        };
+        index += 1;
+    }
+
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::HeuristicTokenizer;
+
+    fn assert_offsets_exact(observation: &Observation, chunks: &[Chunk]) {
+        for chunk in chunks {
+            assert_eq!(
+                &observation.content()[chunk.start_offset()..chunk.end_offset()],
+                chunk.text()
+            );
+        }
+    }
+
+    #[test]
+    fn byte_chunks_respect_char_boundaries() {
+        let observation = Observation::from_content("héllo wörld ✓✓✓").unwrap();
+        let chunks = fixed_size_chunks(
+            &observation,
+            ChunkSize::Bytes(4).into(),
+            &HeuristicTokenizer,
+        )
+        .unwrap();
+
+        assert!(chunks.iter().all(|c| c.text().len() <= 4));
+        assert_offsets_exact(&observation, &chunks);
+        let joined: String = chunks.iter().map(Chunk::text).collect();
+        assert_eq!(joined, observation.content());
+    }
+
+    #[test]
+    fn oversized_char_becomes_its_own_chunk() {
+        let observation = Observation::from_content("✓✓").unwrap();
+        let chunks = fixed_size_chunks(
+            &observation,
+            ChunkSize::Bytes(1).into(),
+            &HeuristicTokenizer,
+        )
+        .unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].text(), "✓");
+    }
+
+    #[test]
+    fn token_chunks_stay_within_budget() {
+        let content = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+        let observation = Observation::from_content(content).unwrap();
+        let tokenizer = HeuristicTokenizer;
+        let chunks =
+            fixed_size_chunks(&observation, ChunkSize::Tokens(16).into(), &tokenizer).unwrap();
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(tokenizer.count_tokens(chunk.text()) <= 16);
+            assert_eq!(
+                chunk.token_estimate() as usize,
+                tokenizer.count_tokens(chunk.text())
+            );
+        }
+        assert_offsets_exact(&observation, &chunks);
+        assert_eq!(
+            chunks.last().unwrap().end_offset(),
+            observation.content().len()
+        );
+    }
+
+    #[test]
+    fn long_words_are_split_to_fit_tokens() {
+        let observation = Observation::from_content("a".repeat(100)).unwrap();
+        let chunks = fixed_size_chunks(
+            &observation,
+            ChunkSize::Tokens(3).into(),
+            &HeuristicTokenizer,
+        )
+        .unwrap();
+
+        assert!(
+            chunks
+                .iter()
+                .all(|c| HeuristicTokenizer.count_tokens(c.text()) <= 3)
+        );
+        assert_offsets_exact(&observation, &chunks);
+    }
+
+    #[test]
+    fn zero_budget_is_rejected() {
+        let observation = Observation::from_content("text").unwrap();
+        assert!(
+            fixed_size_chunks(
+                &observation,
+                ChunkSize::Tokens(0).into(),
+                &HeuristicTokenizer
+            )
+            .is_err()
+        );
+        assert!(
+            fixed_size_chunks(
+                &observation,
+                ChunkSize::Bytes(0).into(),
+                &HeuristicTokenizer
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn byte_overlap_repeats_the_previous_tail() {
+        let observation = Observation::from_content("abcdefghijklmnopqrstuvwxyz").unwrap();
+        let options = ChunkOptions::new(ChunkSize::Bytes(10)).with_overlap(3);
+        let chunks = fixed_size_chunks(&observation, options, &HeuristicTokenizer).unwrap();
+
+        let texts: Vec<&str> = chunks.iter().map(Chunk::text).collect();
+        assert_eq!(texts, ["abcdefghij", "hijklmnopq", "opqrstuvwx", "vwxyz"]);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[1].start_offset(), pair[0].end_offset() - 3);
+            assert_eq!(pair[1].index(), pair[0].index() + 1);
+        }
+        assert_offsets_exact(&observation, &chunks);
+    }
+
+    #[test]
+    fn token_overlap_starts_on_a_word() {
+        let content = "The quick brown fox jumps over the lazy dog. ".repeat(10);
+        let observation = Observation::from_content(content).unwrap();
+        let tokenizer = HeuristicTokenizer;
+        let options = ChunkOptions::new(ChunkSize::Tokens(12)).with_overlap(4);
+        let chunks = fixed_size_chunks(&observation, options, &tokenizer).unwrap();
+
+        for pair in chunks.windows(2) {
+            let (prev, next) = (&pair[0], &pair[1]);
+            assert!(next.start_offset() < prev.end_offset());
+            assert!(next.start_offset() > prev.start_offset());
+            let shared = &observation.content()[next.start_offset()..prev.end_offset()];
+            assert!(tokenizer.count_tokens(shared) <= 4);
+            assert!(shared.starts_with(' ') || shared.starts_with('.'));
+        }
+        assert!(
+            chunks
+                .iter()
+                .all(|c| tokenizer.count_tokens(c.text()) <= 12)
+        );
+        assert_offsets_exact(&observation, &chunks);
+    }
+
+    #[test]
+    fn overlap_must_be_smaller_than_size() {
+        let observation = Observation::from_content("text").unwrap();
+        let options = ChunkOptions::new(ChunkSize::Bytes(4)).with_overlap(4);
+        assert!(fixed_size_chunks(&observation, options, &HeuristicTokenizer).is_err());
+    }
+}
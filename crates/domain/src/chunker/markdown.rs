@@ -0,0 +1,430 @@
+use crate::{chunk::Chunk, observation::Observation, tokenizer::Tokenizer};
+
+use super::{
+    ChunkOptions, Measure, build_chunks,
+    recursive::{Level, merge_segments, segment},
+};
+
+/// Segments that may be packed into the same chunks.
+type Group = Vec<(usize, usize)>;
+
+/// A line of the document, with `end` past its line ending.
+struct Line<'a> {
+    start: usize,
+    end: usize,
+    text: &'a str,
+}
+
+fn lines(text: &str) -> Vec<Line<'_>> {
+    let mut start = 0usize;
+    text.split_inclusive('\n')
+        .map(|raw| {
+            let line = Line {
+                start,
+                end: start + raw.len(),
+                text: raw.trim_end_matches(['\n', '\r']),
+            };
+            start = line.end;
+            line
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum BlockKind {
+    Heading {
+        level: usize,
+        title: String,
+    },
+    Fence,
+    /// A list or table, which may be split between items or rows.
+    Items(Vec<usize>),
+    Paragraph,
+}
+
+#[derive(Debug)]
+struct Block {
+    kind: BlockKind,
+    start: usize,
+    end: usize,
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// The fence char and length if `line` opens a fenced code block.
+fn fence_open(line: &str) -> Option<(char, usize)> {
+    let rest = line.trim_start();
+    let marker = rest.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let len = rest.chars().take_while(|c| *c == marker).count();
+    (len >= 3).then_some((marker, len))
+}
+
+fn fence_closes(line: &str, (marker, len): (char, usize)) -> bool {
+    let rest = line.trim();
+    rest.chars().count() >= len && rest.chars().all(|c| c == marker)
+}
+
+/// Level and title of an ATX heading such as `## Install ##`.
+fn atx_heading(line: &str) -> Option<(usize, String)> {
+    if indent(line) > 3 {
+        return None;
+    }
+    let rest = line.trim_start();
+    let level = rest.chars().take_while(|c| *c == '#').count();
+    let after = &rest[level..];
+    if !(1..=6).contains(&level) || !(after.is_empty() || after.starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    let title = after.trim();
+    let unclosed = title.trim_end_matches('#');
+    let title = if unclosed.is_empty() || unclosed.ends_with([' ', '\t']) {
+        unclosed.trim_end()
+    } else {
+        title
+    };
+    Some((level, title.to_string()))
+}
+
+fn is_list_item(line: &str) -> bool {
+    let rest = line.trim_start();
+    if rest.starts_with(['-', '*', '+']) {
+        return rest[1..].is_empty() || rest[1..].starts_with([' ', '\t']);
+    }
+    let digits = rest.chars().take_while(char::is_ascii_digit).count();
+    let after = &rest[digits..];
+    (1..=9).contains(&digits)
+        && after.starts_with(['.', ')'])
+        && (after[1..].is_empty() || after[1..].starts_with([' ', '\t']))
+}
+
+fn is_table_row(line: &str) -> bool {
+    line.trim_start().starts_with('|')
+}
+
+fn is_blank(line: &Line<'_>) -> bool {
+    line.text.trim().is_empty()
+}
+
+/// Whether `line` starts a block that interrupts a paragraph.
+fn starts_block(line: &str) -> bool {
+    (indent(line) <= 3 && fence_open(line).is_some())
+        || atx_heading(line).is_some()
+        || is_table_row(line)
+        || is_list_item(line)
+}
+
+/// Index of the line closing the fence opened at `open`, or the last line
+/// if it is never closed.
+fn fence_end(lines: &[Line<'_>], open: usize, marker: (char, usize)) -> usize {
+    (open + 1..lines.len())
+        .find(|j| fence_closes(lines[*j].text, marker))
+        .unwrap_or(lines.len() - 1)
+}
+
+/// Splits Markdown into top-level blocks. Blank lines between blocks belong
+/// to none of them.
+fn parse_blocks(text: &str) -> Vec<Block> {
+    let lines = lines(text);
+    let mut blocks = Vec::new();
+    let mut i = 0usize;
+
+    while i < lines.len() {
+        let line = &lines[i];
+        if is_blank(line) {
+            i += 1;
+            continue;
+        }
+
+        if indent(line.text) <= 3
+            && let Some(marker) = fence_open(line.text)
+        {
+            let last = fence_end(&lines, i, marker);
+            blocks.push(Block {
+                kind: BlockKind::Fence,
+                start: line.start,
+                end: lines[last].end,
+            });
+            i = last + 1;
+            continue;
+        }
+
+        if let Some((level, title)) = atx_heading(line.text) {
+            blocks.push(Block {
+                kind: BlockKind::Heading { level, title },
+                start: line.start,
+                end: line.end,
+            });
+            i += 1;
+            continue;
+        }
+
+        if is_table_row(line.text) {
+            let rows: Vec<usize> = lines[i..]
+                .iter()
+                .take_while(|l| is_table_row(l.text))
+                .map(|l| l.start)
+                .collect();
+            i += rows.len();
+            blocks.push(Block {
+                kind: BlockKind::Items(rows),
+                start: line.start,
+                end: lines[i - 1].end,
+            });
+            continue;
+        }
+
+        if is_list_item(line.text) {
+            let mut items = vec![line.start];
+            let mut last = i;
+            let mut j = i + 1;
+            while j < lines.len() {
+                let next = &lines[j];
+                if is_blank(next) {
+                    // A blank line only continues the list if an item or an
+                    // indented continuation follows it.
+                    let Some(k) = (j..lines.len()).find(|k| !is_blank(&lines[*k])) else {
+                        break;
+                    };
+                    if !(is_list_item(lines[k].text) || indent(lines[k].text) >= 2) {
+                        break;
+                    }
+                    j = k;
+                } else if indent(next.text) >= 2
+                    && let Some(marker) = fence_open(next.text)
+                {
+                    // Fences nested in an item stay whole, even if they
+                    // contain lines that look like list items.
+                    last = fence_end(&lines, j, marker);
+                    j = last + 1;
+                } else if is_list_item(next.text) {
+                    items.push(next.start);
+                    last = j;
+                    j += 1;
+                } else if indent(next.text) >= 2 || !starts_block(next.text) {
+                    last = j;
+                    j += 1;
+                } else {
+                    break;
+                }
+            }
+            blocks.push(Block {
+                kind: BlockKind::Items(items),
+                start: line.start,
+                end: lines[last].end,
+            });
+            i = last + 1;
+            continue;
+        }
+
+        let len = lines[i + 1..]
+            .iter()
+            .take_while(|l| !is_blank(l) && !starts_block(l.text))
+            .count();
+        blocks.push(Block {
+            kind: BlockKind::Paragraph,
+            start: line.start,
+            end: lines[i + len].end,
+        });
+        i += len + 1;
+    }
+
+    blocks
+}
+
+/// Splits a block that is over budget into groups of segments. Fences stay
+/// whole; lists and tables split between items, and prose between sentences
+/// and then words.
+fn split_block(text: &str, block: &Block, measure: &Measure<'_>, groups: &mut Vec<Group>) {
+    const PROSE: &[Level] = &[Level::Sentence, Level::Word];
+
+    match &block.kind {
+        BlockKind::Fence => groups.push(vec![(block.start, block.end)]),
+        BlockKind::Items(starts) => {
+            let mut run = Vec::new();
+            let ends = starts.iter().skip(1).copied().chain([block.end]);
+            for item in starts.iter().copied().zip(ends) {
+                if measure.size_of(&text[item.0..item.1]) <= measure.limit() {
+                    run.push(item);
+                    continue;
+                }
+                if !run.is_empty() {
+                    groups.push(std::mem::take(&mut run));
+                }
+                segment(text, item, PROSE, measure, groups);
+            }
+            if !run.is_empty() {
+                groups.push(run);
+            }
+        }
+        BlockKind::Heading { .. } | BlockKind::Paragraph => {
+            segment(text, (block.start, block.end), PROSE, measure, groups);
+        }
+    }
+}
+
+/// Splits Markdown `observation` into chunks that never span two sections
+/// and never cut inside a fenced code block; a fence over budget becomes a
+/// chunk of its own. Whole blocks in a section are packed together up to
+/// the budget, and each chunk records the headings it sits under.
+pub(super) fn markdown_chunks(
+    observation: &Observation,
+    options: ChunkOptions,
+    tokenizer: &dyn Tokenizer,
+) -> Vec<Chunk> {
+    let measure = Measure::new(options.size, tokenizer);
+    let text = observation.content();
+
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    let mut groups: Vec<(Vec<String>, Group)> = Vec::new();
+    let mut run = Vec::new();
+    let mut section_start = None;
+
+    for block in parse_blocks(text) {
+        let path: Vec<String> = headings.iter().map(|(_, title)| title.clone()).collect();
+        let range = (block.start, block.end);
+
+        if let BlockKind::Heading { level, title } = &block.kind {
+            if !run.is_empty() {
+                groups.push((path, std::mem::take(&mut run)));
+            }
+            headings.retain(|(outer, _)| outer < level);
+            headings.push((*level, title.clone()));
+            section_start = Some(block.start);
+            run.push(range);
+            continue;
+        }
+
+        if measure.size_of(&text[block.start..block.end]) <= measure.limit() {
+            run.push(range);
+            continue;
+        }
+
+        let mut pieces = Vec::new();
+        split_block(text, &block, &measure, &mut pieces);
+        // A heading with nothing after it yet joins the block's first
+        // pieces rather than becoming a chunk of its own.
+        if run.len() == 1 && section_start == Some(run[0].0) {
+            if let Some(first) = pieces.first_mut() {
+                first.splice(0..0, run.drain(..));
+            }
+        } else if !run.is_empty() {
+            groups.push((path.clone(), std::mem::take(&mut run)));
+        }
+        groups.extend(pieces.into_iter().map(|group| (path.clone(), group)));
+    }
+    if !run.is_empty() {
+        let path = headings.into_iter().map(|(_, title)| title).collect();
+        groups.push((path, run));
+    }
+
+    let ranges = groups.iter().flat_map(|(path, segments)| {
+        merge_segments(text, segments, &measure, options.overlap)
+            .into_iter()
+            .map(|range| (range, path.clone()))
+    });
+
+    build_chunks(observation, ranges, tokenizer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chunker::{ChunkSize, ChunkStrategy},
+        tokenizer::HeuristicTokenizer,
+    };
+
+    fn markdown_chunks_of(content: &str, size: ChunkSize) -> Vec<Chunk> {
+        let observation = Observation::from_content(content).unwrap();
+        let options = ChunkOptions::new(size).with_strategy(ChunkStrategy::Markdown);
+        let chunks = observation.chunk(options, &HeuristicTokenizer).unwrap();
+        for chunk in &chunks {
+            assert_eq!(
+                &observation.content()[chunk.start_offset()..chunk.end_offset()],
+                chunk.text()
+            );
+        }
+        chunks
+    }
+
+    #[test]
+    fn records_heading_paths() {
+        let content = "Preamble.\n\n# Install\n\nIntro.\n\n## Linux\n\nUse apt.\n\n\
+                       ### Troubleshooting ###\n\nCheck logs.\n\n## macOS\n\nUse brew.\n";
+        let chunks = markdown_chunks_of(content, ChunkSize::Bytes(200));
+
+        let paths: Vec<String> = chunks
+            .iter()
+            .map(|c| c.heading_path().join(" > "))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "",
+                "Install",
+                "Install > Linux",
+                "Install > Linux > Troubleshooting",
+                "Install > macOS"
+            ]
+        );
+        assert_eq!(chunks[2].text(), "## Linux\n\nUse apt.");
+    }
+
+    #[test]
+    fn never_splits_inside_code_fences() {
+        let code = "```rust\nfn main() {\n\n# not a heading\n- not an item\n}\n```";
+        let content = format!("# Usage\n\nRun it like this:\n\n{code}\n\nThat's all.\n");
+        let chunks = markdown_chunks_of(&content, ChunkSize::Bytes(30));
+
+        assert!(chunks.iter().any(|c| c.text() == code));
+        assert!(chunks.iter().all(|c| c.heading_path() == ["Usage"]));
+    }
+
+    #[test]
+    fn splits_lists_and_tables_between_items() {
+        let content = "## Steps\n\n- first step\n- second step\n  continued\n- third step\n\n\
+                       | a | b |\n|---|---|\n| 1 | 2 |\n| 3 | 4 |\n";
+        let chunks = markdown_chunks_of(content, ChunkSize::Bytes(26));
+
+        for chunk in &chunks {
+            assert!(chunk.text().len() <= 26);
+            for line in chunk.text().lines() {
+                let trimmed = line.trim();
+                assert!(
+                    trimmed.is_empty()
+                        || trimmed.starts_with("##")
+                        || trimmed.starts_with('-')
+                        || trimmed.starts_with('|')
+                        || trimmed == "continued"
+                );
+            }
+            assert!(!chunk.text().starts_with("continued"));
+        }
+        assert!(
+            chunks
+                .iter()
+                .any(|c| c.text() == "- second step\n  continued")
+        );
+    }
+
+    #[test]
+    fn parses_block_kinds() {
+        let blocks = parse_blocks("# Title #\n\ntext\nmore\n1. item\n\n    indented\n~~~\ncode\n");
+        let kinds: Vec<&BlockKind> = blocks.iter().map(|b| &b.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &BlockKind::Heading {
+                    level: 1,
+                    title: "Title".to_string()
+                },
+                &BlockKind::Paragraph,
+                &BlockKind::Items(vec![21]),
+                &BlockKind::Fence,
+            ]
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{chunk::Chunk, observation::Observation, tokenizer::Tokenizer};
+
+use super::{ChunkOptions, Measure, build_chunks, next_char_boundary};
+
+/// Characters that end a sentence when followed by whitespace.
+const SENTENCE_ENDS: &[char] = &['.', '!', '?', '…', '。', '！', '？'];
+
+/// Closing quotes and brackets that may sit between a sentence end and the
+/// following whitespace, as in `He said "no."`.
+const CLOSERS: &[char] = &['"', '\'', ')', ']', '”', '’', '»'];
+
+/// The units text is split into, coarsest first.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Level {
+    Paragraph,
+    Sentence,
+    Word,
+}
+
+impl Level {
+    const ALL: [Self; 3] = [Self::Paragraph, Self::Sentence, Self::Word];
+
+    /// Whether the whitespace `run` after the char `last` separates two
+    /// units at this level.
+    fn breaks(self, last: char, run: &str) -> bool {
+        match self {
+            Self::Paragraph => run.matches('\n').count() >= 2,
+            Self::Sentence => run.contains('\n') || SENTENCE_ENDS.contains(&last),
+            Self::Word => true,
+        }
+    }
+}
+
+/// Offsets in `text` where a new unit of `level` starts: just after the
+/// whitespace separating it from the previous one, so separators stay
+/// attached to the unit they end.
+fn split_points(text: &str, level: Level) -> Vec<usize> {
+    let mut points = Vec::new();
+    let mut run_start = None;
+    let mut last = None;
+
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            run_start.get_or_insert(i);
+            continue;
+        }
+        if let Some(start) = run_start.take()
+            && let Some(last) = last
+            && level.breaks(last, &text[start..i])
+        {
+            points.push(i);
+        }
+        if !CLOSERS.contains(&c) {
+            last = Some(c);
+        }
+    }
+
+    points
+}
+
+/// Splits `start..end` into segments that each fit the budget, using the
+/// coarsest of `levels` that gets there and cutting on char boundaries only
+/// when no level does.
+///
+/// Segments are emitted in groups that may be packed together: a run of
+/// whole units at one level, or the pieces of a single unit that was too
+/// big. Pieces of an oversized sentence are thus never packed with the
+/// sentences around it.
+pub(super) fn segment(
+    text: &str,
+    (start, end): (usize, usize),
+    levels: &[Level],
+    measure: &Measure<'_>,
+    groups: &mut Vec<Vec<(usize, usize)>>,
+) {
+    let Some((level, finer)) = levels.split_first() else {
+        let mut pieces = Vec::new();
+        let mut from = start;
+        while from < end {
+            let mut to = from + measure.fit_prefix(&text[from..end], measure.limit());
+            if to == from {
+                to = next_char_boundary(text, from);
+            }
+            pieces.push((from, to));
+            from = to;
+        }
+        groups.push(pieces);
+        return;
+    };
+
+    let mut run = Vec::new();
+    let mut piece_start = start;
+    let points = split_points(&text[start..end], *level);
+    for point in points.into_iter().map(|p| start + p).chain([end]) {
+        let piece = (piece_start, point);
+        piece_start = point;
+
+        if measure.size_of(&text[piece.0..piece.1]) <= measure.limit() {
+            run.push(piece);
+            continue;
+        }
+        if !run.is_empty() {
+            groups.push(std::mem::take(&mut run));
+        }
+        segment(text, piece, finer, measure, groups);
+    }
+    if !run.is_empty() {
+        groups.push(run);
+    }
+}
+
+/// Greedily packs contiguous segments into chunk ranges that fit the
+/// budget. With a non-zero `overlap`, each range after the first starts
+/// with the longest run of the previous range's trailing segments that fits
+/// in `overlap`, as long as the next new segment still fits beside it.
+pub(super) fn merge_segments(
+    text: &str,
+    segments: &[(usize, usize)],
+    measure: &Measure<'_>,
+    overlap: usize,
+) -> Vec<(usize, usize)> {
+    let size = |from: usize, to: usize| measure.size_of(&text[from..to]);
+    let mut ranges = Vec::new();
+    let mut first = 0usize;
+
+    while first < segments.len() {
+        let start = segments[first].0;
+        let fits = |last: usize| size(start, segments[last].1) <= measure.limit();
+
+        // Gallop forward to bound the search, so each probe only measures
+        // about as much text as the chunk itself.
+        let mut step = 1usize;
+        let mut upper = first + 1;
+        while upper < segments.len() && fits(upper) {
+            upper = (first + step * 2).min(segments.len());
+            step *= 2;
+        }
+        let lower = first + step / 2;
+        let last = lower
+            + segments[lower + 1..upper].partition_point(|s| size(start, s.1) <= measure.limit());
+
+        let end = segments[last].1;
+        ranges.push((start, end));
+        let Some(next) = segments.get(last + 1) else {
+            break;
+        };
+
+        let tail = &segments[first + 1..=last];
+        let in_overlap = tail.partition_point(|s| size(s.0, end) > overlap);
+        let beside_next = tail.partition_point(|s| size(s.0, next.1) > measure.limit());
+        first = first + 1 + in_overlap.max(beside_next);
+    }
+
+    ranges
+}
+
+/// Splits `observation` at paragraph breaks where the budget allows, then
+/// at sentence ends, then between words, and only as a last resort between
+/// chars. Adjacent pieces are packed into chunks up to the budget, and
+/// whitespace at the edges of each chunk is left out of its range.
+pub(super) fn recursive_chunks(
+    observation: &Observation,
+    options: ChunkOptions,
+    tokenizer: &dyn Tokenizer,
+) -> Vec<Chunk> {
+    let measure = Measure::new(options.size, tokenizer);
+    let text = observation.content();
+
+    let mut groups = Vec::new();
+    segment(text, (0, text.len()), &Level::ALL, &measure, &mut groups);
+
+    let ranges = groups
+        .iter()
+        .flat_map(|group| merge_segments(text, group, &measure, options.overlap))
+        .map(|range| (range, Vec::new()));
+
+    build_chunks(observation, ranges, tokenizer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chunker::{ChunkSize, ChunkStrategy},
+        tokenizer::HeuristicTokenizer,
+    };
+
+    fn recursive(size: ChunkSize, overlap: usize) -> ChunkOptions {
+        ChunkOptions::new(size)
+            .with_strategy(ChunkStrategy::Recursive)
+            .with_overlap(overlap)
+    }
+
+    fn chunk_texts(content: &str, options: ChunkOptions) -> Vec<String> {
+        let observation = Observation::from_content(content).unwrap();
+        let chunks = observation.chunk(options, &HeuristicTokenizer).unwrap();
+        for chunk in &chunks {
+            assert_eq!(
+                &observation.content()[chunk.start_offset()..chunk.end_offset()],
+                chunk.text()
+            );
+        }
+        chunks.iter().map(|c| c.text().to_string()).collect()
+    }
+
+    #[test]
+    fn prefers_paragraph_breaks() {
+        let content = "First paragraph here.\n\nSecond one, a bit longer.\n\nThird.";
+        let texts = chunk_texts(content, recursive(ChunkSize::Bytes(30), 0));
+        assert_eq!(
+            texts,
+            [
+                "First paragraph here.",
+                "Second one, a bit longer.",
+                "Third."
+            ]
+        );
+    }
+
+    #[test]
+    fn packs_small_paragraphs_together() {
+        let content = "One.\n\nTwo.\n\nThree.";
+        let texts = chunk_texts(content, recursive(ChunkSize::Bytes(12), 0));
+        assert_eq!(texts, ["One.\n\nTwo.", "Three."]);
+    }
+
+    #[test]
+    fn falls_back_to_sentences_then_words() {
+        let content = "Short one. This sentence is far too long to fit whole. End!";
+        let texts = chunk_texts(content, recursive(ChunkSize::Bytes(24), 0));
+
+        assert_eq!(texts[0], "Short one.");
+        assert_eq!(texts.last().unwrap(), "End!");
+        assert!(texts.iter().all(|t| t.len() <= 24));
+        // No word is ever cut.
+        let words: Vec<&str> = content.split_whitespace().collect();
+        let rejoined: Vec<&str> = texts.iter().flat_map(|t| t.split_whitespace()).collect();
+        assert_eq!(rejoined, words);
+    }
+
+    #[test]
+    fn cuts_chars_only_for_oversized_words() {
+        let content = format!("tiny {} tail", "x".repeat(25));
+        let texts = chunk_texts(&content, recursive(ChunkSize::Bytes(10), 0));
+        assert_eq!(texts[0], "tiny");
+        assert_eq!(texts.last().unwrap(), "tail");
+        assert!(texts.iter().all(|t| t.len() <= 10));
+    }
+
+    #[test]
+    fn token_budget_and_overlap() {
+        let content = "The quick brown fox jumps over the lazy dog. ".repeat(12);
+        let observation = Observation::from_content(content).unwrap();
+        // Each sentence is 10 tokens: two fit per chunk, one per overlap.
+        let options = recursive(ChunkSize::Tokens(24), 12);
+        let chunks = observation.chunk(options, &HeuristicTokenizer).unwrap();
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(HeuristicTokenizer.count_tokens(chunk.text()) <= 24);
+        }
+        for pair in chunks.windows(2) {
+            assert!(pair[1].start_offset() < pair[0].end_offset());
+            assert!(pair[1].start_offset() > pair[0].start_offset());
+        }
+        assert_eq!(
+            chunks.last().unwrap().end_offset(),
+            observation.content().trim_end().len()
+        );
+    }
+}
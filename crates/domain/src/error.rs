@@ -9,6 +9,9 @@ pub enum Error {
 
     #[error(transparent)]
     Observation(#[from] ObservationError),
+
+    #[error(transparent)]
+    Tokenizer(#[from] TokenizerError),
 }
 
 #[derive(Debug, Error)]
@@ -19,6 +22,21 @@ pub enum ValidationError {
     #[error("chunk size must be greater than zero")]
     InvalidChunkSize,
 
+    #[error("chunk overlap must be smaller than the chunk size")]
+    InvalidChunkOverlap,
+
+    #[error("unknown chunk strategy: {0}")]
+    UnknownChunkStrategy(String),
+
+    #[error("unknown source kind: {0}")]
+    UnknownSourceKind(String),
+
+    #[error("poll interval must be greater than zero")]
+    InvalidPollInterval,
+
+    #[error("page map spans must be ordered, non-overlapping and within the content")]
+    InvalidPageMap,
+
     #[error("content too large: {size} bytes (max: {max}")]
     ContentTooLarge { size: usize, max: usize },
 
@@ -35,6 +53,18 @@ pub enum ObservationError {
     Duplicate { hash: String },
 }
 
+#[derive(Debug, Error)]
+pub enum TokenizerError {
+    #[error("failed to read tokenizer file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("malformed tokenizer file at line {line}")]
+    Parse { line: usize },
+
+    #[error("tokenizer file has no tokens")]
+    EmptyVocabulary,
+}
+
 impl ValidationError {
     #[must_use]
     pub const fn missing_field(field: &'static str) -> Self {
@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{
+    error::{Result, ValidationError},
+    ids::FeedId,
+};
+
+/// How a single poll of a feed ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedPollStatus {
+    Fetched,
+    NotModified,
+    Failed,
+}
+
+impl FeedPollStatus {
+    pub const fn as_str(&self) -> &str {
+        match self {
+            Self::Fetched => "fetched",
+            Self::NotModified => "not_modified",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Conditional-request validators and bookkeeping from the most recent poll.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FeedPollState {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub last_polled_at: Option<DateTime<Utc>>,
+    /// `None` means the feed has never been polled and is due immediately.
+    pub next_poll_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// A feed subscription that is polled on a fixed interval.
+#[derive(Debug, Clone)]
+pub struct Feed {
+    pub(crate) id: FeedId,
+    pub(crate) url: String,
+    pub(crate) title: Option<String>,
+    pub(crate) poll_interval: Duration,
+    pub(crate) created_at: DateTime<Utc>,
+    pub(crate) state: FeedPollState,
+}
+
+impl Feed {
+    pub fn new(url: impl Into<String>, poll_interval: Duration) -> Result<Self> {
+        let url = url.into().trim().to_string();
+        if url.is_empty() {
+            return Err(ValidationError::EmptyField { field: "url" }.into());
+        }
+
+        if poll_interval.is_zero() {
+            return Err(ValidationError::InvalidPollInterval.into());
+        }
+
+        Ok(Self {
+            id: FeedId::new(),
+            url,
+            title: None,
+            poll_interval,
+            created_at: Utc::now(),
+            state: FeedPollState::default(),
+        })
+    }
+
+    #[must_use]
+    pub const fn reconstruct(
+        id: FeedId,
+        url: String,
+        title: Option<String>,
+        poll_interval: Duration,
+        created_at: DateTime<Utc>,
+        state: FeedPollState,
+    ) -> Self {
+        Self {
+            id,
+            url,
+            title,
+            poll_interval,
+            created_at,
+            state,
+        }
+    }
+
+    #[must_use]
+    pub const fn id(&self) -> FeedId {
+        self.id
+    }
+
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    #[must_use]
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    #[must_use]
+    pub const fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    #[must_use]
+    pub const fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    #[must_use]
+    pub const fn state(&self) -> &FeedPollState {
+        &self.state
+    }
+
+    #[must_use]
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.state.next_poll_at.is_none_or(|next| next <= now)
+    }
+
+    /// When the feed should next be polled, given a poll that happened at `polled_at`.
+    #[must_use]
+    pub fn next_poll_after(&self, polled_at: DateTime<Utc>) -> DateTime<Utc> {
+        let interval = TimeDelta::from_std(self.poll_interval).unwrap_or(TimeDelta::MAX);
+        polled_at
+            .checked_add_signed(interval)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_feed_is_due_immediately() {
+        let feed = Feed::new("https://example.com/feed", Duration::from_secs(60)).unwrap();
+        assert!(feed.is_due(Utc::now()));
+    }
+
+    #[test]
+    fn feed_is_not_due_before_next_poll() {
+        let mut feed = Feed::new("https://example.com/feed", Duration::from_secs(60)).unwrap();
+        let now = Utc::now();
+        feed.state.next_poll_at = Some(feed.next_poll_after(now));
+
+        assert!(!feed.is_due(now));
+        assert!(feed.is_due(now + TimeDelta::seconds(60)));
+    }
+
+    #[test]
+    fn rejects_zero_interval_and_empty_url() {
+        assert!(Feed::new("https://example.com/feed", Duration::ZERO).is_err());
+        assert!(Feed::new("  ", Duration::from_secs(60)).is_err());
+    }
+}
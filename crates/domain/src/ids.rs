@@ -81,6 +81,7 @@ macro_rules! define_id {
 
 define_id!(ObservationId => "observation");
 define_id!(ChunkId => "chunk");
+define_id!(FeedId => "feed");
 
 // Content Hash
 mod hash_serde {
@@ -1,4 +1,9 @@
 pub mod chunk;
+pub mod chunker;
 pub mod error;
+pub mod feed;
 pub mod ids;
 pub mod observation;
+pub mod page;
+pub mod search;
+pub mod tokenizer;
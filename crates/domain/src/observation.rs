@@ -1,9 +1,14 @@
+use std::str::FromStr;
+
 use chrono::{DateTime, Utc};
 
 use crate::{
     chunk::Chunk,
+    chunker::{self, ChunkOptions},
     error::{Result, ValidationError},
     ids::{ContentHash, ObservationId},
+    page::PageMap,
+    tokenizer::Tokenizer,
 };
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -43,6 +48,20 @@ impl SourceKind {
     }
 }
 
+/// Strict counterpart of [`SourceKind::parse`], for user input.
+impl FromStr for SourceKind {
+    type Err = ValidationError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match Self::parse(s) {
+            Self::Unknown if !s.trim().eq_ignore_ascii_case("unknown") => {
+                Err(ValidationError::UnknownSourceKind(s.to_string()))
+            }
+            kind => Ok(kind),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Observation {
     id: ObservationId,
@@ -53,6 +72,8 @@ pub struct Observation {
     source_kind: SourceKind,
     created_at: DateTime<Utc>,
     published_at: Option<DateTime<Utc>>,
+    language: Option<String>,
+    page_map: Option<PageMap>,
 }
 
 impl Observation {
@@ -105,38 +126,21 @@ impl Observation {
         &self.content
     }
 
-    pub fn chunk(&self, chunk_size: usize) -> Result<Vec<Chunk>> {
-        if chunk_size == 0 {
-            return Err(ValidationError::InvalidChunkSize.into());
-        }
-
-        let mut chunks = Vec::new();
-        let text = self.content.as_str();
-
-        let mut start = 0usize;
-        let mut index = 0i32;
-
-        while start < text.len() {
-            let mut end = (start + chunk_size).min(text.len());
-            while end > start && !text.is_char_boundary(end) {
-                end -= 1;
-            }
-
-            if end == start {
-                end = (start + chunk_size).min(text.len());
-                while end < text.len() && !text.is_char_boundary(end) {
-                    end += 1;
-                }
-            }
-
-            let chunk_text = &text[start..end];
-            chunks.push(Chunk::new(self, index, chunk_text, start, end));
+    /// Language of the content as a BCP 47 tag such as `en` or `de-AT`,
+    /// when the source declares one.
+    #[must_use]
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
 
-            start = end;
-            index += 1;
-        }
+    /// Page boundaries for content extracted from paginated sources such as PDFs.
+    #[must_use]
+    pub const fn page_map(&self) -> Option<&PageMap> {
+        self.page_map.as_ref()
+    }
 
-        Ok(chunks)
+    pub fn chunk(&self, options: ChunkOptions, tokenizer: &dyn Tokenizer) -> Result<Vec<Chunk>> {
+        chunker::chunk(self, options, tokenizer)
     }
 }
 
@@ -147,6 +151,8 @@ pub struct ObservationBuilder {
     source_url: Option<String>,
     source_kind: SourceKind,
     published_at: Option<DateTime<Utc>>,
+    language: Option<String>,
+    page_map: Option<PageMap>,
     // Allow pre-setting ID for testing or reconstruction
     id: Option<ObservationId>,
     created_at: Option<DateTime<Utc>>,
@@ -183,6 +189,21 @@ impl ObservationBuilder {
         self
     }
 
+    /// Sets the content language. Blank tags are ignored.
+    #[must_use]
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        let language = language.into();
+        let language = language.trim();
+        self.language = (!language.is_empty()).then(|| language.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn page_map(mut self, page_map: PageMap) -> Self {
+        self.page_map = Some(page_map);
+        self
+    }
+
     #[must_use]
     pub const fn with_id(mut self, id: ObservationId) -> Self {
         self.id = Some(id);
@@ -204,6 +225,17 @@ impl ObservationBuilder {
             return Err(ValidationError::EmptyContent.into());
         }
 
+        if let Some(page_map) = &self.page_map {
+            let fits = page_map.content_len() <= content.len()
+                && page_map.spans().iter().all(|s| {
+                    content.is_char_boundary(s.start_offset)
+                        && content.is_char_boundary(s.end_offset)
+                });
+            if !fits {
+                return Err(ValidationError::InvalidPageMap.into());
+            }
+        }
+
         Ok(Observation {
             id: self.id.unwrap_or_default(),
             content_hash: ContentHash::from_content(&content),
@@ -213,6 +245,8 @@ impl ObservationBuilder {
             source_kind: self.source_kind,
             created_at: self.created_at.unwrap_or_else(Utc::now),
             published_at: self.published_at,
+            language: self.language,
+            page_map: self.page_map,
         })
     }
 }
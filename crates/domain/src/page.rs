@@ -0,0 +1,116 @@
+use crate::error::{Result, ValidationError};
+
+/// The byte range of one page within an observation's content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageSpan {
+    pub number: u32,
+    pub start_offset: usize,
+    pub end_offset: usize,
+}
+
+/// Maps byte offsets in an observation's content back to source pages, so
+/// chunks of paginated documents can be cited by page number.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageMap {
+    spans: Vec<PageSpan>,
+}
+
+impl PageMap {
+    /// Spans must be ordered, non-overlapping and numbered in increasing order.
+    pub fn new(spans: Vec<PageSpan>) -> Result<Self> {
+        let mut previous: Option<&PageSpan> = None;
+        for span in &spans {
+            if span.start_offset > span.end_offset {
+                return Err(ValidationError::InvalidPageMap.into());
+            }
+            if let Some(prev) = previous
+                && (span.number <= prev.number || span.start_offset < prev.end_offset)
+            {
+                return Err(ValidationError::InvalidPageMap.into());
+            }
+            previous = Some(span);
+        }
+
+        Ok(Self { spans })
+    }
+
+    #[must_use]
+    pub fn spans(&self) -> &[PageSpan] {
+        &self.spans
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// The end offset of the last page.
+    #[must_use]
+    pub fn content_len(&self) -> usize {
+        self.spans.last().map_or(0, |s| s.end_offset)
+    }
+
+    /// The page containing `offset`. Offsets that fall in the separator
+    /// between two pages belong to the following page.
+    #[must_use]
+    pub fn page_at(&self, offset: usize) -> Option<u32> {
+        self.spans
+            .iter()
+            .find(|s| offset < s.end_offset)
+            .map(|s| s.number)
+    }
+
+    /// First and last page covered by the byte range `start..end`.
+    #[must_use]
+    pub fn pages_for_range(&self, start: usize, end: usize) -> Option<(u32, u32)> {
+        let overlapping = self
+            .spans
+            .iter()
+            .filter(|s| s.start_offset < end && start < s.end_offset);
+
+        let mut first = None;
+        let mut last = None;
+        for span in overlapping {
+            first.get_or_insert(span.number);
+            last = Some(span.number);
+        }
+
+        first.zip(last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(number: u32, start_offset: usize, end_offset: usize) -> PageSpan {
+        PageSpan {
+            number,
+            start_offset,
+            end_offset,
+        }
+    }
+
+    #[test]
+    fn maps_ranges_to_pages() {
+        // "page one\n\npage two\n\npage three"
+        let map = PageMap::new(vec![span(1, 0, 8), span(2, 10, 18), span(3, 20, 30)]).unwrap();
+
+        assert_eq!(map.page_at(0), Some(1));
+        assert_eq!(map.page_at(9), Some(2));
+        assert_eq!(map.page_at(29), Some(3));
+        assert_eq!(map.page_at(30), None);
+
+        assert_eq!(map.pages_for_range(2, 6), Some((1, 1)));
+        assert_eq!(map.pages_for_range(5, 25), Some((1, 3)));
+        assert_eq!(map.pages_for_range(8, 10), None);
+        assert_eq!(map.content_len(), 30);
+    }
+
+    #[test]
+    fn rejects_overlapping_or_unordered_spans() {
+        assert!(PageMap::new(vec![span(1, 0, 10), span(2, 5, 20)]).is_err());
+        assert!(PageMap::new(vec![span(2, 0, 10), span(1, 10, 20)]).is_err());
+        assert!(PageMap::new(vec![span(1, 10, 0)]).is_err());
+    }
+}
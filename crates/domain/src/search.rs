@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+
+use crate::{chunk::Chunk, ids::ObservationId, observation::SourceKind};
+
+/// Which of an observation's timestamps a date filter applies to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DateField {
+    /// When the source published the content, or when it was ingested if
+    /// the source gave no date.
+    #[default]
+    Published,
+    /// When the observation was ingested.
+    Created,
+}
+
+/// Restricts which chunks a search may return. Empty filters match every
+/// chunk; set filters must all match.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SearchFilters {
+    /// Observations of any of these kinds, or of any kind when empty.
+    pub source_kinds: Vec<SourceKind>,
+    pub date_field: DateField,
+    /// Inclusive lower bound on the date in `date_field`.
+    pub since: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on the date in `date_field`.
+    pub until: Option<DateTime<Utc>>,
+    /// Source URLs on this host or one of its subdomains.
+    pub domain: Option<String>,
+    /// Titles containing this text, ignoring case.
+    pub title_contains: Option<String>,
+}
+
+impl SearchFilters {
+    /// Whether no filter is set, so every chunk matches.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.source_kinds.is_empty()
+            && self.since.is_none()
+            && self.until.is_none()
+            && self.domain.is_none()
+            && self.title_contains.is_none()
+    }
+
+    #[must_use]
+    pub fn with_source_kind(mut self, source_kind: SourceKind) -> Self {
+        self.source_kinds.push(source_kind);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_date_field(mut self, date_field: DateField) -> Self {
+        self.date_field = date_field;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Sets the domain filter. A leading `www.` or `.` is ignored.
+    #[must_use]
+    pub fn with_domain(mut self, domain: impl Into<String>) -> Self {
+        let domain = domain.into().trim().to_ascii_lowercase();
+        let domain = domain.trim_start_matches('.');
+        self.domain = Some(domain.strip_prefix("www.").unwrap_or(domain).to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn with_title_contains(mut self, text: impl Into<String>) -> Self {
+        self.title_contains = Some(text.into());
+        self
+    }
+}
+
+/// A search request, shared by keyword, vector and hybrid search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub text: String,
+    /// Most results to return.
+    pub limit: usize,
+    pub filters: SearchFilters,
+}
+
+impl SearchQuery {
+    pub fn new(text: impl Into<String>, limit: usize) -> Self {
+        Self {
+            text: text.into(),
+            limit,
+            filters: SearchFilters::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_filters(mut self, filters: SearchFilters) -> Self {
+        self.filters = filters;
+        self
+    }
+}
+
+/// The parts of an observation a search result is displayed and cited with.
+#[derive(Debug, Clone)]
+pub struct ObservationSummary {
+    pub id: ObservationId,
+    pub title: Option<String>,
+    pub source_url: Option<String>,
+    pub source_kind: SourceKind,
+    pub created_at: DateTime<Utc>,
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// A chunk matched by a search, with the observation it belongs to.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub chunk: Chunk,
+    pub observation: ObservationSummary,
+    /// How well the chunk matches; higher is better. Only comparable
+    /// between hits of the same search.
+    pub score: f32,
+    /// Excerpt of the chunk around the matched terms, for keyword matches.
+    pub snippet: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_domain() {
+        let filters = SearchFilters::default().with_domain(" WWW.Example.com");
+        assert_eq!(filters.domain.as_deref(), Some("example.com"));
+    }
+
+    #[test]
+    fn parses_source_kinds_strictly() {
+        assert_eq!("RSS".parse::<SourceKind>().unwrap(), SourceKind::Rss);
+        assert_eq!(
+            "unknown".parse::<SourceKind>().unwrap(),
+            SourceKind::Unknown
+        );
+        assert!("podcast".parse::<SourceKind>().is_err());
+    }
+}
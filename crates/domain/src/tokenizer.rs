@@ -0,0 +1,337 @@
+use std::{collections::HashMap, path::Path};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use crate::{
+    error::{Result, TokenizerError},
+    ids::ContentHash,
+};
+
+/// Counts model tokens in a piece of text.
+pub trait Tokenizer: Send + Sync {
+    fn count_tokens(&self, text: &str) -> usize;
+
+    /// Names the tokenizer and its vocabulary, so that text counted with
+    /// another one can be told apart.
+    fn id(&self) -> &str;
+}
+
+/// A fast approximation of GPT-style BPE token counts that needs no
+/// vocabulary: ASCII words cost about one token per six letters, other
+/// scripts about one token per character.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HeuristicTokenizer;
+
+impl Tokenizer for HeuristicTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        pre_tokenize(text)
+            .map(|piece| {
+                if piece.is_ascii() {
+                    piece.trim_start().len().div_ceil(6).max(1)
+                } else {
+                    piece.chars().filter(|c| !c.is_whitespace()).count().max(1)
+                }
+            })
+            .sum()
+    }
+
+    fn id(&self) -> &str {
+        "heuristic"
+    }
+}
+
+/// Byte-level BPE tokenizer using a tiktoken rank file, the format of
+/// OpenAI's `cl100k_base.tiktoken` and `o200k_base.tiktoken`: one
+/// `<base64 token> <rank>` pair per line.
+#[derive(Debug, Clone)]
+pub struct BpeTokenizer {
+    id: String,
+    ranks: HashMap<Vec<u8>, u32>,
+}
+
+impl BpeTokenizer {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let data = std::fs::read_to_string(path).map_err(TokenizerError::Io)?;
+        Self::from_tiktoken(&data)
+    }
+
+    pub fn from_tiktoken(data: &str) -> Result<Self> {
+        let mut ranks = HashMap::new();
+
+        for (i, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let parse_error = || TokenizerError::Parse { line: i + 1 };
+            let (token, rank) = line.split_once(' ').ok_or_else(parse_error)?;
+            let token = STANDARD.decode(token).map_err(|_| parse_error())?;
+            let rank: u32 = rank.trim().parse().map_err(|_| parse_error())?;
+            ranks.insert(token, rank);
+        }
+
+        if ranks.is_empty() {
+            return Err(TokenizerError::EmptyVocabulary.into());
+        }
+
+        let hash = ContentHash::from_content(data).to_hex();
+        Ok(Self {
+            id: format!("bpe-{}", &hash[..16]),
+            ranks,
+        })
+    }
+
+    /// Encodes `text` into token ranks.
+    #[must_use]
+    pub fn encode(&self, text: &str) -> Vec<u32> {
+        let mut tokens = Vec::new();
+        for piece in pre_tokenize(text) {
+            self.encode_piece(piece.as_bytes(), &mut tokens);
+        }
+        tokens
+    }
+
+    fn encode_piece(&self, piece: &[u8], out: &mut Vec<u32>) {
+        if let Some(rank) = self.ranks.get(piece) {
+            out.push(*rank);
+            return;
+        }
+
+        // Start from single bytes and repeatedly merge the adjacent pair with
+        // the lowest rank until no adjacent pair is in the vocabulary.
+        let mut bounds: Vec<usize> = (0..=piece.len()).collect();
+        loop {
+            let best = bounds
+                .windows(3)
+                .enumerate()
+                .filter_map(|(i, w)| self.ranks.get(&piece[w[0]..w[2]]).map(|rank| (*rank, i)))
+                .min();
+
+            let Some((_, i)) = best else {
+                break;
+            };
+            bounds.remove(i + 1);
+        }
+
+        // Bytes missing from the vocabulary still cost a token each.
+        out.extend(bounds.windows(2).map(|w| {
+            self.ranks
+                .get(&piece[w[0]..w[1]])
+                .copied()
+                .unwrap_or(u32::MAX)
+        }));
+    }
+}
+
+impl Tokenizer for BpeTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        self.encode(text).len()
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Letter,
+    Number,
+    Newline,
+    Space,
+    Other,
+}
+
+fn classify(c: char) -> CharClass {
+    if c == '\n' || c == '\r' {
+        CharClass::Newline
+    } else if c.is_whitespace() {
+        CharClass::Space
+    } else if c.is_alphabetic() {
+        CharClass::Letter
+    } else if c.is_numeric() {
+        CharClass::Number
+    } else {
+        CharClass::Other
+    }
+}
+
+const CONTRACTIONS: &[&str] = &["s", "t", "re", "ve", "m", "ll", "d"];
+
+/// Splits text the way GPT-style tokenizers do before applying BPE: words
+/// keep a single leading space or punctuation mark, numbers are split into
+/// groups of at most three digits, punctuation runs stay together, and
+/// whitespace before a word is attached to that word.
+///
+/// Every piece starts and ends on a char boundary, and concatenating the
+/// pieces reproduces the input.
+pub fn pre_tokenize(text: &str) -> impl Iterator<Item = &str> {
+    PreTokenizer { text, pos: 0 }
+}
+
+struct PreTokenizer<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for PreTokenizer<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.text[self.pos..];
+        let len = next_piece_len(rest);
+        if len == 0 {
+            return None;
+        }
+        self.pos += len;
+        Some(&rest[..len])
+    }
+}
+
+/// Length of the next pre-token, following the alternatives of the
+/// `cl100k_base` split pattern in order.
+fn next_piece_len(rest: &str) -> usize {
+    let mut chars = rest.chars();
+    let Some(first) = chars.next() else {
+        return 0;
+    };
+    let first_len = first.len_utf8();
+    let first_class = classify(first);
+    let second_class = chars.next().map(classify);
+
+    // End of the run of chars matching `pred`, starting at byte `from`.
+    let run_end = |from: usize, pred: &dyn Fn(char) -> bool| {
+        rest[from..]
+            .char_indices()
+            .find(|(_, c)| !pred(*c))
+            .map_or(rest.len(), |(i, _)| from + i)
+    };
+    let is_class = |class: CharClass| move |c: char| classify(c) == class;
+
+    // 's 't 're 've 'm 'll 'd
+    if first == '\'' {
+        let after = &rest[1..];
+        if let Some(suffix) = CONTRACTIONS.iter().find(|suffix| {
+            after
+                .get(..suffix.len())
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case(suffix))
+        }) {
+            return 1 + suffix.len();
+        }
+    }
+
+    // Letters, optionally led by one space or punctuation mark.
+    if first_class == CharClass::Letter {
+        return run_end(0, &is_class(CharClass::Letter));
+    }
+    if matches!(first_class, CharClass::Space | CharClass::Other)
+        && second_class == Some(CharClass::Letter)
+    {
+        return run_end(first_len, &is_class(CharClass::Letter));
+    }
+
+    // Up to three digits.
+    if first_class == CharClass::Number {
+        let end = run_end(0, &is_class(CharClass::Number));
+        return rest[..end].char_indices().nth(3).map_or(end, |(i, _)| i);
+    }
+
+    // Punctuation, optionally led by a space and followed by newlines.
+    let punct_start = if first == ' ' && second_class == Some(CharClass::Other) {
+        1
+    } else {
+        0
+    };
+    if classify_at(rest, punct_start) == CharClass::Other {
+        let end = run_end(punct_start, &is_class(CharClass::Other));
+        return run_end(end, &is_class(CharClass::Newline));
+    }
+
+    // Whitespace: up to and including the last newline of the run, or else
+    // all but the last char when a word follows, so it can lead that word.
+    let end = run_end(0, &char::is_whitespace);
+    if let Some(newline) = rest[..end].rfind(['\n', '\r']) {
+        return newline + 1;
+    }
+    if end < rest.len() && end > first_len {
+        return end - rest[..end].chars().last().map_or(0, char::len_utf8);
+    }
+    end
+}
+
+fn classify_at(text: &str, pos: usize) -> CharClass {
+    text[pos..]
+        .chars()
+        .next()
+        .map_or(CharClass::Space, classify)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pre_tokenize_reassembles_input() {
+        let text = "Hello, world! It's 12345 crabs.\n\n  Ünïcödé   text\tend ";
+        let pieces: Vec<_> = pre_tokenize(text).collect();
+        assert_eq!(pieces.concat(), text);
+        assert!(pieces.contains(&" world"));
+        assert!(pieces.contains(&"'s"));
+        assert!(pieces.contains(&"123"));
+        assert!(pieces.contains(&"45"));
+    }
+
+    #[test]
+    fn heuristic_is_close_for_english() {
+        let text = "The quick brown fox jumps over the lazy dog.";
+        assert_eq!(HeuristicTokenizer.count_tokens(text), 10);
+        assert_eq!(HeuristicTokenizer.count_tokens(""), 0);
+    }
+
+    fn tiny_vocab() -> String {
+        let mut lines: Vec<String> = (0u8..=255)
+            .map(|b| format!("{} {b}", STANDARD.encode([b])))
+            .collect();
+        for (rank, token) in ["he", "ll", "hell", "hello", " w", " wo", " wor"]
+            .iter()
+            .enumerate()
+        {
+            lines.push(format!("{} {}", STANDARD.encode(token), 256 + rank));
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn bpe_merges_by_rank() {
+        let bpe = BpeTokenizer::from_tiktoken(&tiny_vocab()).unwrap();
+
+        assert_eq!(bpe.encode("hello"), vec![259]);
+        // " world" -> " wor" + "l" + "d"
+        assert_eq!(
+            bpe.encode("hello world"),
+            vec![259, 262, u32::from(b'l'), u32::from(b'd')]
+        );
+        assert_eq!(bpe.count_tokens("hello world"), 4);
+    }
+
+    #[test]
+    fn bpe_id_follows_the_vocabulary() {
+        let vocab = tiny_vocab();
+        let bpe = BpeTokenizer::from_tiktoken(&vocab).unwrap();
+        let same = BpeTokenizer::from_tiktoken(&vocab).unwrap();
+        let other = BpeTokenizer::from_tiktoken(&format!("{vocab}\naGk= 999")).unwrap();
+
+        assert!(bpe.id().starts_with("bpe-"));
+        assert_eq!(bpe.id(), same.id());
+        assert_ne!(bpe.id(), other.id());
+        assert_ne!(bpe.id(), HeuristicTokenizer.id());
+    }
+
+    #[test]
+    fn bpe_rejects_malformed_files() {
+        assert!(BpeTokenizer::from_tiktoken("not-base64! 1").is_err());
+        assert!(BpeTokenizer::from_tiktoken("aGk= notanumber").is_err());
+        assert!(BpeTokenizer::from_tiktoken("").is_err());
+    }
+}
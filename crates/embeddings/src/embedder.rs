@@ -0,0 +1,120 @@
+use async_trait::async_trait;
+
+use crate::error::{EmbedError, Result};
+
+/// Turns text into fixed-dimension vectors with a single embedding model.
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Identifier of the model, stored next to every vector it produces so
+    /// vectors from different models are never compared.
+    fn model_id(&self) -> &str;
+
+    /// Length of every vector the model returns.
+    fn dimension(&self) -> usize;
+
+    /// Largest number of inputs [`Embedder::embed`] accepts in one call.
+    fn max_batch_size(&self) -> usize {
+        64
+    }
+
+    /// Embeds a batch of at most [`Embedder::max_batch_size`] texts,
+    /// returning one vector per input, in input order.
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>>;
+
+    /// Embeds any number of texts, split into batches the model accepts.
+    async fn embed_all(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.max_batch_size().max(1)) {
+            vectors.extend(self.embed(batch).await?);
+        }
+        Ok(vectors)
+    }
+}
+
+/// Checks that a model returned one `dimension`-long vector per input.
+pub fn check_batch(
+    inputs: usize,
+    dimension: usize,
+    vectors: Vec<Vec<f32>>,
+) -> Result<Vec<Vec<f32>>> {
+    if vectors.len() != inputs {
+        return Err(EmbedError::CountMismatch {
+            expected: inputs,
+            actual: vectors.len(),
+        });
+    }
+    if let Some(bad) = vectors.iter().find(|v| v.len() != dimension) {
+        return Err(EmbedError::DimensionMismatch {
+            expected: dimension,
+            actual: bad.len(),
+        });
+    }
+    Ok(vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Embeds each text as its length, counting calls.
+    struct LengthEmbedder {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Embedder for LengthEmbedder {
+        fn model_id(&self) -> &str {
+            "length"
+        }
+
+        fn dimension(&self) -> usize {
+            1
+        }
+
+        fn max_batch_size(&self) -> usize {
+            2
+        }
+
+        async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+            assert!(texts.len() <= self.max_batch_size());
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            let vectors = texts.iter().map(|t| vec![t.len() as f32]).collect();
+            check_batch(texts.len(), self.dimension(), vectors)
+        }
+    }
+
+    #[tokio::test]
+    async fn embed_all_batches_in_order() {
+        let embedder = LengthEmbedder {
+            calls: AtomicUsize::new(0),
+        };
+        let vectors = embedder
+            .embed_all(&["a", "bb", "ccc", "dddd", "eeeee"])
+            .await
+            .unwrap();
+
+        assert_eq!(vectors, [[1.0], [2.0], [3.0], [4.0], [5.0]]);
+        assert_eq!(embedder.calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn check_batch_rejects_wrong_shapes() {
+        assert!(matches!(
+            check_batch(2, 3, vec![vec![0.0; 3]]),
+            Err(EmbedError::CountMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+        assert!(matches!(
+            check_batch(1, 3, vec![vec![0.0; 4]]),
+            Err(EmbedError::DimensionMismatch {
+                expected: 3,
+                actual: 4
+            })
+        ));
+        assert!(check_batch(0, 3, Vec::new()).unwrap().is_empty());
+    }
+}
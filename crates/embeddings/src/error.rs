@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, EmbedError>;
+
+#[derive(Debug, Error)]
+pub enum EmbedError {
+    #[error("rate limited by the embedding provider")]
+    RateLimited { retry_after: Option<Duration> },
+
+    #[error("input too large to embed: {0}")]
+    InputTooLarge(String),
+
+    #[error("embedding transport failed: {0}")]
+    Transport(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("embedding provider returned {status}: {message}")]
+    Provider { status: u16, message: String },
+
+    #[error("local embedding model failed: {0}")]
+    Model(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("expected {expected} embeddings, got {actual}")]
+    CountMismatch { expected: usize, actual: usize },
+
+    #[error("expected {expected}-dimensional embeddings, got {actual}")]
+    DimensionMismatch { expected: usize, actual: usize },
+}
+
+impl EmbedError {
+    /// Whether the same request may succeed if sent again later.
+    #[must_use]
+    pub const fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimited { .. } | Self::Transport(_) => true,
+            Self::Provider { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use async_trait::async_trait;
+
+use crate::{
+    embedder::{Embedder, check_batch},
+    error::Result,
+};
+
+/// Dimension used when none is given.
+const DEFAULT_DIMENSION: usize = 256;
+
+/// Embeds text by feature hashing its character n-grams, with no model
+/// weights and no network access.
+///
+/// Each lower-cased word is padded with spaces and cut into overlapping
+/// n-grams; every n-gram adds ±1 to the slot its hash picks, and the vector
+/// is L2-normalized. Texts sharing many n-grams get similar vectors, so
+/// rankings behave sensibly for tests, and the same text always gets the
+/// same vector on every platform.
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    model_id: String,
+    dimension: usize,
+    ngram: usize,
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(DEFAULT_DIMENSION)
+    }
+}
+
+impl HashingEmbedder {
+    /// An embedder of trigrams into `dimension` slots (at least one).
+    #[must_use]
+    pub fn new(dimension: usize) -> Self {
+        let dimension = dimension.max(1);
+        Self {
+            model_id: format!("hashing-3gram-{dimension}"),
+            dimension,
+            ngram: 3,
+        }
+    }
+
+    /// Uses n-grams of `ngram` characters (at least one) instead of
+    /// trigrams.
+    #[must_use]
+    pub fn with_ngram(mut self, ngram: usize) -> Self {
+        self.ngram = ngram.max(1);
+        self.model_id = format!("hashing-{}gram-{}", self.ngram, self.dimension);
+        self
+    }
+
+    #[must_use]
+    pub fn with_model_id(mut self, model_id: impl Into<String>) -> Self {
+        self.model_id = model_id.into();
+        self
+    }
+
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimension];
+
+        for word in text.split(|c: char| !c.is_alphanumeric()) {
+            if word.is_empty() {
+                continue;
+            }
+            let padded: Vec<char> = std::iter::once(' ')
+                .chain(word.chars().flat_map(char::to_lowercase))
+                .chain(std::iter::once(' '))
+                .collect();
+
+            for gram in padded.windows(self.ngram.min(padded.len())) {
+                let hash = fnv1a(gram);
+                let slot = (hash % self.dimension as u64) as usize;
+                let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+                vector[slot] += sign;
+            }
+        }
+
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            for x in &mut vector {
+                *x /= norm;
+            }
+        }
+        vector
+    }
+}
+
+/// 64-bit FNV-1a over the characters' UTF-8 bytes, which unlike the
+/// standard library's hasher is the same in every build.
+fn fnv1a(chars: &[char]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    let mut buf = [0u8; 4];
+    for c in chars {
+        for byte in c.encode_utf8(&mut buf).bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+#[async_trait]
+impl Embedder for HashingEmbedder {
+    fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn max_batch_size(&self) -> usize {
+        1024
+    }
+
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let vectors = texts.iter().map(|text| self.embed_one(text)).collect();
+        check_batch(texts.len(), self.dimension, vectors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[tokio::test]
+    async fn similar_texts_get_similar_vectors() {
+        let embedder = HashingEmbedder::default();
+        let vectors = embedder
+            .embed(&[
+                "Crabs walk sideways along the shore",
+                "A crab walking sideways on the shore",
+                "Quarterly revenue grew by four percent",
+            ])
+            .await
+            .unwrap();
+
+        let similar = cosine(&vectors[0], &vectors[1]);
+        let unrelated = cosine(&vectors[0], &vectors[2]);
+        assert!(similar > 0.5, "similar = {similar}");
+        assert!(unrelated < 0.2, "unrelated = {unrelated}");
+        assert!((cosine(&vectors[0], &vectors[0]) - 1.0).abs() < 1e-5);
+    }
+
+    #[tokio::test]
+    async fn is_deterministic_and_case_insensitive() {
+        let embedder = HashingEmbedder::new(64).with_ngram(4);
+        let a = embedder
+            .embed(&["Hermit Crab", "hermit crab"])
+            .await
+            .unwrap();
+        let b = HashingEmbedder::new(64)
+            .with_ngram(4)
+            .embed(&["Hermit Crab"])
+            .await
+            .unwrap();
+
+        assert_eq!(a[0], a[1]);
+        assert_eq!(a[0], b[0]);
+        assert_eq!(a[0].len(), 64);
+        assert_eq!(embedder.model_id(), "hashing-4gram-64");
+    }
+
+    #[tokio::test]
+    async fn empty_text_is_the_zero_vector() {
+        let vectors = HashingEmbedder::new(8).embed(&["", " -- "]).await.unwrap();
+        assert!(vectors.iter().flatten().all(|&x| x == 0.0));
+    }
+}
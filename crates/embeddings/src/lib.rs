@@ -1,14 +1,13 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+pub mod embedder;
+pub mod error;
+pub mod hashing;
+pub mod local;
+pub mod openai;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use crate::{
+    embedder::{Embedder, check_batch},
+    error::{EmbedError, Result},
+    hashing::HashingEmbedder,
+    local::LocalEmbedder,
+    openai::{OpenAiConfig, OpenAiEmbedder},
+};
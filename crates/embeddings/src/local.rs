@@ -0,0 +1,365 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use candle_core::{D, DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+
+use crate::{
+    embedder::{Embedder, check_batch},
+    error::{EmbedError, Result},
+};
+
+/// Sequence length used when the model directory does not set one.
+const DEFAULT_MAX_SEQ_LENGTH: usize = 256;
+
+/// The optional `sentence_bert_config.json` of a sentence-transformers model.
+#[derive(Deserialize)]
+struct SentenceBertConfig {
+    max_seq_length: Option<usize>,
+}
+
+/// The model name `transformers` records in `config.json` when saving.
+#[derive(Deserialize)]
+struct ModelName {
+    #[serde(rename = "_name_or_path")]
+    name_or_path: Option<String>,
+}
+
+struct LocalModel {
+    model: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+}
+
+/// Runs a BERT-style sentence-transformers model (such as
+/// `all-MiniLM-L6-v2`) on the CPU, with mean pooling and L2 normalization.
+///
+/// Inputs longer than the model's maximum sequence length are truncated,
+/// as sentence-transformers does.
+pub struct LocalEmbedder {
+    inner: Arc<LocalModel>,
+    model_id: String,
+    dimension: usize,
+    batch_size: usize,
+}
+
+impl LocalEmbedder {
+    /// Loads a model directory holding `config.json`, `tokenizer.json` and
+    /// `model.safetensors`.
+    ///
+    /// The model id is the model's name (its `_name_or_path`, or else the
+    /// directory name) followed by a hash of its config and weights, so
+    /// fine-tuned copies and same-named directories don't share embeddings.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let config: Config = read_json(&dir.join("config.json"))?;
+        let max_seq_length =
+            read_json::<SentenceBertConfig>(&dir.join("sentence_bert_config.json"))
+                .ok()
+                .and_then(|c| c.max_seq_length)
+                .unwrap_or(DEFAULT_MAX_SEQ_LENGTH)
+                .min(config.max_position_embeddings);
+
+        let mut tokenizer =
+            Tokenizer::from_file(dir.join("tokenizer.json")).map_err(EmbedError::Model)?;
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: max_seq_length,
+                ..TruncationParams::default()
+            }))
+            .map_err(EmbedError::Model)?;
+        tokenizer.with_padding(Some(PaddingParams {
+            pad_id: u32::try_from(config.pad_token_id).unwrap_or_default(),
+            ..PaddingParams::default()
+        }));
+
+        let device = Device::Cpu;
+        let weights = [dir.join("model.safetensors")];
+        // SAFETY: the weights file is memory-mapped read-only and must not
+        // be modified while the model is loaded.
+        let vb = unsafe { VarBuilder::from_mmaped_safetensors(&weights, DTYPE, &device) }
+            .map_err(model_error)?;
+        let model = BertModel::load(vb, &config).map_err(model_error)?;
+
+        Ok(Self {
+            inner: Arc::new(LocalModel {
+                model,
+                tokenizer,
+                device,
+            }),
+            model_id: model_id(dir)?,
+            dimension: config.hidden_size,
+            batch_size: 32,
+        })
+    }
+
+    #[must_use]
+    pub fn with_model_id(mut self, model_id: impl Into<String>) -> Self {
+        self.model_id = model_id.into();
+        self
+    }
+
+    #[must_use]
+    pub const fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+}
+
+impl LocalModel {
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.iter().map(String::as_str).collect(), true)
+            .map_err(EmbedError::Model)?;
+
+        let ids = encodings
+            .iter()
+            .map(|e| Tensor::new(e.get_ids(), &self.device))
+            .collect::<candle_core::Result<Vec<_>>>()
+            .map_err(model_error)?;
+        let masks = encodings
+            .iter()
+            .map(|e| Tensor::new(e.get_attention_mask(), &self.device))
+            .collect::<candle_core::Result<Vec<_>>>()
+            .map_err(model_error)?;
+
+        self.forward(&ids, &masks).map_err(model_error)
+    }
+
+    fn forward(&self, ids: &[Tensor], masks: &[Tensor]) -> candle_core::Result<Vec<Vec<f32>>> {
+        let input_ids = Tensor::stack(ids, 0)?;
+        let attention_mask = Tensor::stack(masks, 0)?;
+        let token_type_ids = input_ids.zeros_like()?;
+
+        let hidden = self
+            .model
+            .forward(&input_ids, &token_type_ids, Some(&attention_mask))?;
+
+        // Mean over the real tokens only, so padding never changes a vector.
+        let mask = attention_mask.to_dtype(DType::F32)?.unsqueeze(D::Minus1)?;
+        let summed = hidden.broadcast_mul(&mask)?.sum(1)?;
+        let counts = mask.sum(1)?.clamp(1e-9, f64::MAX)?;
+        let pooled = summed.broadcast_div(&counts)?;
+
+        let norms = pooled
+            .sqr()?
+            .sum_keepdim(1)?
+            .sqrt()?
+            .clamp(1e-12, f64::MAX)?;
+        pooled.broadcast_div(&norms)?.to_vec2()
+    }
+}
+
+#[async_trait]
+impl Embedder for LocalEmbedder {
+    fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn max_batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let inner = Arc::clone(&self.inner);
+        let owned: Vec<String> = texts.iter().map(|t| (*t).to_string()).collect();
+        let vectors = tokio::task::spawn_blocking(move || inner.embed(&owned))
+            .await
+            .map_err(|e| EmbedError::Model(Box::new(e)))??;
+
+        check_batch(texts.len(), self.dimension, vectors)
+    }
+}
+
+fn model_error(e: candle_core::Error) -> EmbedError {
+    EmbedError::Model(Box::new(e))
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T> {
+    let data = std::fs::read_to_string(path).map_err(|e| EmbedError::Model(Box::new(e)))?;
+    serde_json::from_str(&data).map_err(|e| EmbedError::Model(Box::new(e)))
+}
+
+fn model_id(dir: &Path) -> Result<String> {
+    let config_path = dir.join("config.json");
+    let name = read_json::<ModelName>(&config_path)?
+        .name_or_path
+        .as_deref()
+        .and_then(|name| {
+            name.trim_end_matches(['/', '\\'])
+                .rsplit(['/', '\\'])
+                .next()
+        })
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .or_else(|| {
+            std::fs::canonicalize(dir)
+                .unwrap_or_else(|_| PathBuf::from(dir))
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "local".to_string());
+
+    let mut hasher = Sha256::new();
+    for path in [config_path, dir.join("model.safetensors")] {
+        let mut file = std::fs::File::open(path).map_err(|e| EmbedError::Model(Box::new(e)))?;
+        std::io::copy(&mut file, &mut hasher).map_err(|e| EmbedError::Model(Box::new(e)))?;
+    }
+    let hash = hex::encode(hasher.finalize());
+
+    Ok(format!("{name}-{}", &hash[..16]))
+}
+
+#[cfg(test)]
+mod tests {
+    use candle_nn::VarMap;
+    use serde_json::json;
+
+    use super::*;
+
+    const VOCAB: &[&str] = &[
+        "[PAD]", "[UNK]", "[CLS]", "[SEP]", "the", "crab", "walks", "sideways", "on", "sand", ".",
+    ];
+
+    /// Writes a tiny randomly initialised BERT model in the
+    /// sentence-transformers layout.
+    fn write_tiny_model(dir: &Path) {
+        let config = json!({
+            "vocab_size": VOCAB.len(),
+            "hidden_size": 8,
+            "num_hidden_layers": 1,
+            "num_attention_heads": 2,
+            "intermediate_size": 16,
+            "hidden_act": "gelu",
+            "hidden_dropout_prob": 0.0,
+            "max_position_embeddings": 32,
+            "type_vocab_size": 2,
+            "initializer_range": 0.02,
+            "layer_norm_eps": 1e-12,
+            "pad_token_id": 0,
+            "model_type": "bert"
+        });
+        std::fs::write(dir.join("config.json"), config.to_string()).unwrap();
+        std::fs::write(
+            dir.join("sentence_bert_config.json"),
+            json!({ "max_seq_length": 6 }).to_string(),
+        )
+        .unwrap();
+
+        let vocab: serde_json::Map<_, _> = VOCAB
+            .iter()
+            .enumerate()
+            .map(|(i, token)| ((*token).to_string(), json!(i)))
+            .collect();
+        let tokenizer = json!({
+            "version": "1.0",
+            "truncation": null,
+            "padding": null,
+            "added_tokens": [],
+            "normalizer": { "type": "BertNormalizer", "clean_text": true,
+                "handle_chinese_chars": true, "strip_accents": null, "lowercase": true },
+            "pre_tokenizer": { "type": "BertPreTokenizer" },
+            "post_processor": { "type": "BertProcessing", "sep": ["[SEP]", 3], "cls": ["[CLS]", 2] },
+            "decoder": null,
+            "model": { "type": "WordPiece", "unk_token": "[UNK]",
+                "continuing_subword_prefix": "##", "max_input_chars_per_word": 100, "vocab": vocab }
+        });
+        std::fs::write(dir.join("tokenizer.json"), tokenizer.to_string()).unwrap();
+
+        let config: Config = serde_json::from_value(config).unwrap();
+        let varmap = VarMap::new();
+        let vb = VarBuilder::from_varmap(&varmap, DTYPE, &Device::Cpu);
+        BertModel::load(vb, &config).unwrap();
+        varmap.save(dir.join("model.safetensors")).unwrap();
+    }
+
+    fn tiny_model_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("crabtrap-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_tiny_model(&dir);
+        dir
+    }
+
+    #[test]
+    fn model_id_follows_the_name_and_weights() {
+        let root = tiny_model_dir("local-model-ids");
+        let (a, b, copy) = (
+            root.join("a/model"),
+            root.join("b/model"),
+            root.join("copy"),
+        );
+        for dir in [&a, &b, &copy] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        write_tiny_model(&a);
+        write_tiny_model(&b);
+        for file in ["config.json", "tokenizer.json", "model.safetensors"] {
+            std::fs::copy(a.join(file), copy.join(file)).unwrap();
+        }
+
+        let id = |dir: &Path| LocalEmbedder::load(dir).unwrap().model_id().to_string();
+        let (id_a, id_b, id_copy) = (id(&a), id(&b), id(&copy));
+        assert!(id_a.starts_with("model-"));
+        assert_ne!(id_a, id_b);
+        assert_eq!(id_copy.strip_prefix("copy"), id_a.strip_prefix("model"));
+
+        let mut config: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(a.join("config.json")).unwrap()).unwrap();
+        config["_name_or_path"] = json!("sentence-transformers/all-MiniLM-L6-v2/");
+        std::fs::write(a.join("config.json"), config.to_string()).unwrap();
+        assert!(id(&a).starts_with("all-MiniLM-L6-v2-"));
+        assert_ne!(
+            id(&a).strip_prefix("all-MiniLM-L6-v2"),
+            id_a.strip_prefix("model")
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn embeds_normalized_vectors_independent_of_padding() {
+        let dir = tiny_model_dir("local-embedder");
+        let embedder = LocalEmbedder::load(&dir).unwrap();
+        assert_eq!(embedder.dimension(), 8);
+        assert!(embedder.model_id().starts_with("crabtrap-local-embedder"));
+
+        let batch = embedder
+            .embed(&["the crab", "the crab walks sideways on the sand ."])
+            .await
+            .unwrap();
+        let alone = embedder.embed(&["the crab"]).await.unwrap();
+
+        for vector in &batch {
+            let norm: f32 = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+            assert!((norm - 1.0).abs() < 1e-4);
+        }
+        for (a, b) in batch[0].iter().zip(&alone[0]) {
+            assert!((a - b).abs() < 1e-4);
+        }
+        assert_ne!(batch[0], batch[1]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_model_files_are_an_error() {
+        let err = LocalEmbedder::load("/nonexistent/model").err().unwrap();
+        assert!(matches!(err, EmbedError::Model(_)));
+    }
+}
@@ -0,0 +1,380 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{StatusCode, header::RETRY_AFTER};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    embedder::{Embedder, check_batch},
+    error::{EmbedError, Result},
+};
+
+/// Upper bound on any single wait between retries.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Connection and batching settings for an [`OpenAiEmbedder`].
+#[derive(Debug, Clone)]
+pub struct OpenAiConfig {
+    /// API root including the version prefix, e.g. `http://localhost:8000/v1`.
+    pub base_url: String,
+    pub model: String,
+    /// Length of the vectors the model returns.
+    pub dimension: usize,
+    pub api_key: Option<String>,
+    pub batch_size: usize,
+    /// How many times a rate-limited or failed request is sent again.
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for each one after it. A
+    /// `Retry-After` header from the server takes precedence.
+    pub initial_backoff: Duration,
+}
+
+impl OpenAiConfig {
+    pub fn new(base_url: impl Into<String>, model: impl Into<String>, dimension: usize) -> Self {
+        Self {
+            base_url: base_url.into(),
+            model: model.into(),
+            dimension,
+            api_key: None,
+            batch_size: 64,
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+        }
+    }
+
+    #[must_use]
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    #[must_use]
+    pub const fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_BACKOFF)
+    }
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [&'a str],
+    encoding_format: &'static str,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Embedder for servers speaking the OpenAI `/v1/embeddings` protocol,
+/// such as OpenAI itself, vLLM, llama.cpp server and TEI.
+pub struct OpenAiEmbedder {
+    client: reqwest::Client,
+    config: OpenAiConfig,
+}
+
+impl OpenAiEmbedder {
+    pub fn new(config: OpenAiConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(120))
+            .build()
+            .map_err(|e| EmbedError::Transport(Box::new(e)))?;
+        Ok(Self::with_client(client, config))
+    }
+
+    #[must_use]
+    pub const fn with_client(client: reqwest::Client, config: OpenAiConfig) -> Self {
+        Self { client, config }
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> &OpenAiConfig {
+        &self.config
+    }
+
+    /// Sends one request, without retrying.
+    async fn request(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let url = format!("{}/embeddings", self.config.base_url.trim_end_matches('/'));
+        let body = EmbeddingRequest {
+            model: &self.config.model,
+            input: texts,
+            encoding_format: "float",
+        };
+
+        let mut request = self.client.post(url).json(&body);
+        if let Some(api_key) = &self.config.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| EmbedError::Transport(Box::new(e)))?;
+
+        let status = response.status();
+        if status.is_success() {
+            let mut body: EmbeddingResponse =
+                response.json().await.map_err(|e| EmbedError::Provider {
+                    status: status.as_u16(),
+                    message: format!("invalid response body: {e}"),
+                })?;
+            body.data.sort_by_key(|d| d.index);
+            return Ok(body.data.into_iter().map(|d| d.embedding).collect());
+        }
+
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_secs);
+        let message = error_message(&response.text().await.unwrap_or_default());
+
+        Err(match status {
+            StatusCode::TOO_MANY_REQUESTS => EmbedError::RateLimited { retry_after },
+            StatusCode::PAYLOAD_TOO_LARGE => EmbedError::InputTooLarge(message),
+            StatusCode::BAD_REQUEST if mentions_input_length(&message) => {
+                EmbedError::InputTooLarge(message)
+            }
+            _ => EmbedError::Provider {
+                status: status.as_u16(),
+                message,
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl Embedder for OpenAiEmbedder {
+    fn model_id(&self) -> &str {
+        &self.config.model
+    }
+
+    fn dimension(&self) -> usize {
+        self.config.dimension
+    }
+
+    fn max_batch_size(&self) -> usize {
+        self.config.batch_size
+    }
+
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut attempt = 0u32;
+        loop {
+            match self.request(texts).await {
+                Ok(vectors) => return check_batch(texts.len(), self.dimension(), vectors),
+                Err(e) if e.is_retryable() && attempt < self.config.max_retries => {
+                    let delay = match e {
+                        EmbedError::RateLimited {
+                            retry_after: Some(after),
+                        } => after.min(MAX_BACKOFF),
+                        _ => self.config.backoff(attempt),
+                    };
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Pulls the human-readable message out of the error bodies used by
+/// OpenAI (`{"error": {"message": ..}}`), TEI (`{"error": ..}`), Cohere
+/// (`{"message": ..}`) and others, falling back to the raw body.
+pub fn error_message(body: &str) -> String {
+    let json: Option<serde_json::Value> = serde_json::from_str(body).ok();
+    json.as_ref()
+        .and_then(|v| {
+            v.pointer("/error/message")
+                .or_else(|| v.get("error"))
+                .or_else(|| v.get("message"))
+        })
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or(body)
+        .trim()
+        .to_string()
+}
+
+/// Whether a `400 Bad Request` message blames the length of the input.
+fn mentions_input_length(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    [
+        "context length",
+        "too long",
+        "too large",
+        "maximum input",
+        "max_tokens",
+    ]
+    .iter()
+    .any(|hint| message.contains(hint))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_partial_json, header, method, path},
+    };
+
+    use super::*;
+
+    fn embedder(server: &MockServer) -> OpenAiEmbedder {
+        let config = OpenAiConfig::new(format!("{}/v1", server.uri()), "test-model", 2)
+            .with_api_key("secret")
+            .with_initial_backoff(Duration::from_millis(1));
+        OpenAiEmbedder::new(config).unwrap()
+    }
+
+    fn ok_body(vectors: &[(usize, [f32; 2])]) -> ResponseTemplate {
+        let data: Vec<_> = vectors
+            .iter()
+            .map(|(index, embedding)| json!({ "object": "embedding", "index": index, "embedding": embedding }))
+            .collect();
+        ResponseTemplate::new(200).set_body_json(json!({ "object": "list", "data": data }))
+    }
+
+    #[tokio::test]
+    async fn embeds_in_input_order() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/embeddings"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(
+                json!({ "model": "test-model", "input": ["a", "b"] }),
+            ))
+            .respond_with(ok_body(&[(1, [0.0, 1.0]), (0, [1.0, 0.0])]))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let vectors = embedder(&server).embed(&["a", "b"]).await.unwrap();
+        assert_eq!(vectors, [[1.0, 0.0], [0.0, 1.0]]);
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limits_and_server_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ok_body(&[(0, [0.5, 0.5])]))
+            .mount(&server)
+            .await;
+
+        let vectors = embedder(&server).embed(&["a"]).await.unwrap();
+        assert_eq!(vectors, [[0.5, 0.5]]);
+    }
+
+    #[tokio::test]
+    async fn retry_after_takes_precedence_over_backoff() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ok_body(&[(0, [0.5, 0.5])]))
+            .mount(&server)
+            .await;
+
+        let config = OpenAiConfig::new(format!("{}/v1", server.uri()), "test-model", 2)
+            .with_initial_backoff(Duration::from_secs(3600));
+        let embedder = OpenAiEmbedder::new(config).unwrap();
+        let vectors = tokio::time::timeout(Duration::from_secs(5), embedder.embed(&["a"]))
+            .await
+            .expect("waited for the backoff instead of Retry-After")
+            .unwrap();
+        assert_eq!(vectors, [[0.5, 0.5]]);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(500).set_body_json(json!({ "error": { "message": "boom" } })),
+            )
+            .expect(4)
+            .mount(&server)
+            .await;
+
+        let err = embedder(&server).embed(&["a"]).await.unwrap_err();
+        assert!(
+            matches!(err, EmbedError::Provider { status: 500, ref message } if message == "boom")
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_input_is_not_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+                "error": { "message": "This model's maximum context length is 8192 tokens" }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let err = embedder(&server).embed(&["a"]).await.unwrap_err();
+        assert!(matches!(err, EmbedError::InputTooLarge(_)));
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_dimension() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{ "index": 0, "embedding": [1.0, 2.0, 3.0] }]
+            })))
+            .mount(&server)
+            .await;
+
+        let err = embedder(&server).embed(&["a"]).await.unwrap_err();
+        assert!(matches!(
+            err,
+            EmbedError::DimensionMismatch {
+                expected: 2,
+                actual: 3
+            }
+        ));
+    }
+}
@@ -0,0 +1,36 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, IngestError>;
+
+#[derive(Debug, Error)]
+pub enum IngestError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to parse feed: {0}")]
+    Feed(#[from] feed_rs::parser::ParseFeedError),
+
+    #[error("unsupported content type: {0}")]
+    UnsupportedContentType(String),
+
+    #[error("no readable content found at {0}")]
+    NoReadableContent(String),
+
+    #[error("failed to read PDF: {0}")]
+    Pdf(#[from] lopdf::Error),
+
+    #[error("failed to extract PDF text: {0}")]
+    PdfExtract(#[from] pdf_extract::OutputError),
+
+    #[error("PDF is encrypted")]
+    PdfEncrypted,
+
+    #[error("PDF has no extractable text (image-only or scanned?)")]
+    PdfNoText,
+
+    #[error(transparent)]
+    Domain(#[from] domain::error::Error),
+}
@@ -0,0 +1,395 @@
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use domain::observation::{Observation, SourceKind};
+use feed_rs::model::{Entry, Link};
+use reqwest::{
+    StatusCode,
+    header::{ETAG, HeaderMap, HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+};
+
+use crate::{USER_AGENT, error::Result, html};
+
+/// Where a feed document is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedSource {
+    Url(String),
+    File(PathBuf),
+}
+
+impl FeedSource {
+    /// The URL entry links are resolved against, if the feed came from the web.
+    #[must_use]
+    pub fn base_url(&self) -> Option<&str> {
+        match self {
+            Self::Url(url) => Some(url),
+            Self::File(_) => None,
+        }
+    }
+}
+
+impl FromStr for FeedSource {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        if lower.starts_with("http://") || lower.starts_with("https://") {
+            Ok(Self::Url(s.trim().to_string()))
+        } else {
+            Ok(Self::File(PathBuf::from(s)))
+        }
+    }
+}
+
+impl fmt::Display for FeedSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Url(url) => write!(f, "{url}"),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// The observations extracted from a single feed document.
+#[derive(Debug)]
+pub struct ParsedFeed {
+    pub title: Option<String>,
+    pub observations: Vec<Observation>,
+    /// Entries that had neither content, summary nor title.
+    pub skipped: usize,
+}
+
+/// Result of a conditional GET against a feed URL.
+#[derive(Debug)]
+pub enum FetchOutcome {
+    NotModified,
+    Fetched {
+        body: Vec<u8>,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+}
+
+pub struct FeedIngester {
+    client: reqwest::Client,
+}
+
+impl FeedIngester {
+    pub fn new() -> Result<Self> {
+        let client = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
+        Ok(Self::with_client(client))
+    }
+
+    #[must_use]
+    pub const fn with_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    #[must_use]
+    pub const fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    pub async fn load(&self, source: &FeedSource) -> Result<Vec<u8>> {
+        match source {
+            FeedSource::Url(url) => {
+                let response = self.client.get(url).send().await?.error_for_status()?;
+                Ok(response.bytes().await?.to_vec())
+            }
+            FeedSource::File(path) => Ok(std::fs::read(path)?),
+        }
+    }
+
+    /// Fetches `url`, sending the validators from a previous response so an
+    /// unchanged feed comes back as `304 Not Modified`.
+    pub async fn fetch_conditional(
+        &self,
+        url: &str,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> Result<FetchOutcome> {
+        let mut request = self.client.get(url);
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(FetchOutcome::NotModified);
+        }
+
+        let response = response.error_for_status()?;
+        let headers = response.headers();
+        let etag = header_string(headers, ETAG);
+        let last_modified = header_string(headers, LAST_MODIFIED);
+        let body = response.bytes().await?.to_vec();
+
+        Ok(FetchOutcome::Fetched {
+            body,
+            etag,
+            last_modified,
+        })
+    }
+
+    pub async fn ingest(&self, source: &FeedSource) -> Result<ParsedFeed> {
+        let bytes = self.load(source).await?;
+        parse_feed(&bytes, source.base_url())
+    }
+}
+
+fn header_string(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Parses an RSS, Atom or JSON feed into one `SourceKind::Rss` observation
+/// per entry.
+pub fn parse_feed(bytes: &[u8], base_url: Option<&str>) -> Result<ParsedFeed> {
+    let feed = feed_rs::parser::Builder::new()
+        .base_uri(base_url)
+        .build()
+        .parse(bytes)?;
+
+    let mut observations = Vec::with_capacity(feed.entries.len());
+    let mut skipped = 0usize;
+
+    for entry in &feed.entries {
+        match entry_to_observation(entry, feed.language.as_deref())? {
+            Some(observation) => observations.push(observation),
+            None => skipped += 1,
+        }
+    }
+
+    Ok(ParsedFeed {
+        title: feed.title.map(|t| html::fragment_to_text(&t.content)),
+        observations,
+        skipped,
+    })
+}
+
+fn entry_to_observation(entry: &Entry, feed_language: Option<&str>) -> Result<Option<Observation>> {
+    let title = entry
+        .title
+        .as_ref()
+        .map(|t| html::fragment_to_text(&t.content))
+        .filter(|t| !t.is_empty());
+
+    let body = entry
+        .content
+        .as_ref()
+        .and_then(|c| c.body.as_deref())
+        .or_else(|| entry.summary.as_ref().map(|s| s.content.as_str()))
+        .map(html::fragment_to_text)
+        .filter(|b| !b.is_empty());
+
+    let Some(content) = body.or_else(|| title.clone()) else {
+        return Ok(None);
+    };
+
+    let mut builder = Observation::builder()
+        .content(content)
+        .source_kind(SourceKind::Rss);
+
+    if let Some(title) = title {
+        builder = builder.title(title);
+    }
+
+    if let Some(link) = entry_link(&entry.links) {
+        builder = builder.source_url(link);
+    }
+
+    if let Some(published_at) = entry.published.or(entry.updated) {
+        builder = builder.published_at(published_at);
+    }
+
+    if let Some(language) = entry.language.as_deref().or(feed_language) {
+        builder = builder.language(language);
+    }
+
+    Ok(Some(builder.build()?))
+}
+
+/// Picks the entry's permalink: the `alternate` link if there is one,
+/// otherwise the first link.
+fn entry_link(links: &[Link]) -> Option<&str> {
+    links
+        .iter()
+        .find(|l| l.rel.as_deref().is_none_or(|rel| rel == "alternate"))
+        .or_else(|| links.first())
+        .map(|l| l.href.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0">
+  <channel>
+    <title>Example Feed</title>
+    <link>https://example.com/</link>
+    <language>en-us</language>
+    <item>
+      <title>First post</title>
+      <link>https://example.com/first</link>
+      <description>&lt;p&gt;Hello &lt;b&gt;world&lt;/b&gt;&lt;/p&gt;</description>
+      <pubDate>Tue, 06 Jan 2026 10:00:00 GMT</pubDate>
+    </item>
+    <item>
+      <title>Title only</title>
+      <link>https://example.com/second</link>
+    </item>
+    <item>
+      <link>https://example.com/empty</link>
+    </item>
+  </channel>
+</rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Atom Example</title>
+  <id>urn:example</id>
+  <updated>2026-01-02T00:00:00Z</updated>
+  <entry>
+    <title>Atom entry</title>
+    <id>urn:example:1</id>
+    <link rel="edit" href="https://example.com/edit/1"/>
+    <link rel="alternate" href="https://example.com/atom/1"/>
+    <updated>2026-01-02T00:00:00Z</updated>
+    <content type="html">&lt;p&gt;Atom body&lt;/p&gt;</content>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn parses_rss_items_into_observations() {
+        let parsed = parse_feed(RSS.as_bytes(), None).unwrap();
+
+        assert_eq!(parsed.title.as_deref(), Some("Example Feed"));
+        assert_eq!(parsed.observations.len(), 2);
+        assert_eq!(parsed.skipped, 1);
+
+        let first = &parsed.observations[0];
+        assert_eq!(first.source_kind(), SourceKind::Rss);
+        assert_eq!(first.content(), "Hello world");
+        assert_eq!(first.title(), Some("First post"));
+        assert_eq!(first.source_url(), Some("https://example.com/first"));
+        assert_eq!(first.language(), Some("en-us"));
+        assert_eq!(
+            first.published_at().unwrap().to_rfc3339(),
+            "2026-01-06T10:00:00+00:00"
+        );
+
+        let second = &parsed.observations[1];
+        assert_eq!(second.content(), "Title only");
+        assert!(second.published_at().is_none());
+    }
+
+    #[test]
+    fn parses_atom_and_prefers_alternate_link() {
+        let parsed = parse_feed(ATOM.as_bytes(), None).unwrap();
+        let entry = &parsed.observations[0];
+
+        assert_eq!(entry.content(), "Atom body");
+        assert_eq!(entry.source_url(), Some("https://example.com/atom/1"));
+        assert!(entry.published_at().is_some());
+    }
+
+    #[test]
+    fn refetched_feed_hashes_identically() {
+        let a = parse_feed(RSS.as_bytes(), None).unwrap();
+        let b = parse_feed(RSS.as_bytes(), None).unwrap();
+        assert_eq!(
+            a.observations[0].content_hash(),
+            b.observations[0].content_hash()
+        );
+    }
+
+    #[test]
+    fn feed_source_detects_urls() {
+        assert_eq!(
+            "https://example.com/feed.xml"
+                .parse::<FeedSource>()
+                .unwrap(),
+            FeedSource::Url("https://example.com/feed.xml".into())
+        );
+        assert_eq!(
+            "feeds/local.xml".parse::<FeedSource>().unwrap(),
+            FeedSource::File(PathBuf::from("feeds/local.xml"))
+        );
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(parse_feed(b"not a feed", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn conditional_fetch_returns_not_modified_for_matching_etag() {
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{header, method, path},
+        };
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/feed.xml"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/feed.xml"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v1\"")
+                    .insert_header("last-modified", "Tue, 06 Jan 2026 10:00:00 GMT")
+                    .set_body_string(RSS),
+            )
+            .mount(&server)
+            .await;
+
+        let ingester = FeedIngester::new().unwrap();
+        let url = format!("{}/feed.xml", server.uri());
+
+        let FetchOutcome::Fetched {
+            body,
+            etag,
+            last_modified,
+        } = ingester.fetch_conditional(&url, None, None).await.unwrap()
+        else {
+            panic!("expected a fresh fetch");
+        };
+        assert_eq!(etag.as_deref(), Some("\"v1\""));
+        assert_eq!(
+            last_modified.as_deref(),
+            Some("Tue, 06 Jan 2026 10:00:00 GMT")
+        );
+        assert_eq!(parse_feed(&body, Some(&url)).unwrap().observations.len(), 2);
+
+        let second = ingester
+            .fetch_conditional(&url, etag.as_deref(), last_modified.as_deref())
+            .await
+            .unwrap();
+        assert!(matches!(second, FetchOutcome::NotModified));
+    }
+
+    #[tokio::test]
+    async fn conditional_fetch_surfaces_http_errors() {
+        use wiremock::{Mock, MockServer, ResponseTemplate, matchers::method};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let ingester = FeedIngester::new().unwrap();
+        let result = ingester.fetch_conditional(&server.uri(), None, None).await;
+        assert!(result.is_err());
+    }
+}
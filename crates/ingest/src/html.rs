@@ -0,0 +1,120 @@
+use scraper::{ElementRef, Html, Node};
+
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "noscript", "template"];
+
+/// Converts an HTML fragment into plain text, keeping paragraph breaks
+/// between block-level elements.
+#[must_use]
+pub fn fragment_to_text(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    element_to_text(fragment.root_element())
+}
+
+/// Converts the subtree rooted at `element` into plain text.
+#[must_use]
+pub fn element_to_text(element: ElementRef<'_>) -> String {
+    let mut raw = String::new();
+    collect_text(element, &mut raw);
+    normalize_whitespace(&raw)
+}
+
+fn collect_text(element: ElementRef<'_>, out: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(text),
+            Node::Element(el) => {
+                let name = el.name();
+                if SKIPPED_ELEMENTS.contains(&name) {
+                    continue;
+                }
+                if name == "br" {
+                    out.push('\n');
+                    continue;
+                }
+
+                let is_block = BLOCK_ELEMENTS.contains(&name);
+                if is_block {
+                    out.push_str("\n\n");
+                }
+                if let Some(child) = ElementRef::wrap(child) {
+                    collect_text(child, out);
+                }
+                if is_block {
+                    out.push_str("\n\n");
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Collapses runs of inline whitespace to a single space and runs of blank
+/// lines to a single paragraph break.
+fn normalize_whitespace(raw: &str) -> String {
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+
+    for line in raw.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join("\n"));
+                current.clear();
+            }
+        } else {
+            current.push(line);
+        }
+    }
+
+    if !current.is_empty() {
+        paragraphs.push(current.join("\n"));
+    }
+
+    paragraphs.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_paragraph_breaks_and_drops_scripts() {
+        let html = "<p>First   paragraph</p><script>alert(1)</script><p>Second<br>line</p>";
+        assert_eq!(fragment_to_text(html), "First paragraph\n\nSecond\nline");
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!(fragment_to_text("Fish &amp; chips"), "Fish & chips");
+    }
+}
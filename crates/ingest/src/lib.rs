@@ -1,14 +1,14 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+pub mod error;
+pub mod feed;
+mod html;
+pub mod pdf;
+pub mod web;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+pub use crate::{
+    error::{IngestError, Result},
+    feed::{FeedIngester, FeedSource, FetchOutcome, ParsedFeed, parse_feed},
+    pdf::{PdfText, extract_pdf, load_pdf},
+    web::{Article, WebIngester, extract_article},
+};
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub(crate) const USER_AGENT: &str = concat!("crabtrap/", env!("CARGO_PKG_VERSION"));
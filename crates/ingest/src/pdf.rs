@@ -0,0 +1,329 @@
+use std::path::Path;
+
+use domain::{
+    observation::{Observation, SourceKind},
+    page::{PageMap, PageSpan},
+};
+use lopdf::{Document, Object, decode_text_string};
+use pdf_extract::{PlainTextOutput, output_doc_page};
+
+use crate::error::{IngestError, Result};
+
+/// Separator placed between the text of consecutive pages.
+const PAGE_SEPARATOR: &str = "\n\n";
+
+/// Text extracted from a PDF, one entry per page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdfText {
+    pub title: Option<String>,
+    pub pages: Vec<String>,
+}
+
+impl PdfText {
+    /// Joins the pages into a single string and records where each page
+    /// starts and ends in it.
+    #[must_use]
+    pub fn join_pages(&self) -> (String, Vec<PageSpan>) {
+        let mut content = String::new();
+        let mut spans = Vec::with_capacity(self.pages.len());
+
+        for (i, page) in self.pages.iter().enumerate() {
+            if i > 0 {
+                content.push_str(PAGE_SEPARATOR);
+            }
+            let start_offset = content.len();
+            content.push_str(page);
+            spans.push(PageSpan {
+                number: u32::try_from(i + 1).unwrap_or(u32::MAX),
+                start_offset,
+                end_offset: content.len(),
+            });
+        }
+
+        (content, spans)
+    }
+
+    pub fn into_observation(self, source_url: Option<String>) -> Result<Observation> {
+        let (content, spans) = self.join_pages();
+
+        let mut builder = Observation::builder()
+            .content(content)
+            .source_kind(SourceKind::Pdf)
+            .page_map(PageMap::new(spans)?);
+
+        if let Some(title) = self.title {
+            builder = builder.title(title);
+        }
+
+        if let Some(source_url) = source_url {
+            builder = builder.source_url(source_url);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// Extracts the text of every page of a PDF.
+///
+/// Documents encrypted with an empty user password are decrypted as they
+/// are loaded. Fails with [`IngestError::PdfEncrypted`] for documents that
+/// need a password, and with [`IngestError::PdfNoText`] for documents without a text
+/// layer (e.g. scans), rather than producing an empty observation.
+pub fn extract_pdf(bytes: &[u8]) -> Result<PdfText> {
+    let doc = Document::load_mem(bytes)?;
+
+    // Loading already decrypts documents that open with an empty password
+    // and records their encryption state, but keeps `Encrypt` in the
+    // trailer; decrypting them again would garble their text.
+    if doc.is_encrypted() && doc.encryption_state.is_none() {
+        return Err(IngestError::PdfEncrypted);
+    }
+
+    let mut pages = Vec::new();
+    for page_number in doc.get_pages().keys() {
+        let mut raw = String::new();
+        {
+            let mut output = PlainTextOutput::new(&mut raw);
+            output_doc_page(&doc, &mut output, *page_number)?;
+        }
+        pages.push(normalize_page(&raw));
+    }
+
+    if pages.iter().all(|p| p.is_empty()) {
+        return Err(IngestError::PdfNoText);
+    }
+
+    Ok(PdfText {
+        title: document_title(&doc),
+        pages,
+    })
+}
+
+/// Reads a local PDF into a `SourceKind::Pdf` observation with a page map.
+/// The title falls back to the file name when the document has none.
+pub fn load_pdf(path: &Path) -> Result<Observation> {
+    let bytes = std::fs::read(path)?;
+    let mut text = extract_pdf(&bytes)?;
+
+    if text.title.is_none() {
+        text.title = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned());
+    }
+
+    let source_url = std::fs::canonicalize(path)
+        .ok()
+        .and_then(|abs| url::Url::from_file_path(abs).ok())
+        .map(|url| url.to_string());
+
+    text.into_observation(source_url)
+}
+
+fn document_title(doc: &Document) -> Option<String> {
+    let info = doc.trailer.get(b"Info").ok()?;
+    let (_, info) = doc.dereference(info).ok()?;
+    let title = info.as_dict().ok()?.get(b"Title").ok()?;
+    let (_, title) = doc.dereference(title).ok()?;
+
+    match title {
+        Object::String(..) => decode_text_string(title).ok(),
+        _ => None,
+    }
+    .map(|t| t.trim().to_string())
+    .filter(|t| !t.is_empty())
+}
+
+/// Trims trailing whitespace from each line and collapses runs of blank
+/// lines, so page text carries paragraph breaks but no layout padding.
+fn normalize_page(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut blank_run = 0usize;
+
+    for line in raw.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            blank_run += 1;
+            continue;
+        }
+
+        if !out.is_empty() {
+            out.push_str(if blank_run > 0 { "\n\n" } else { "\n" });
+        }
+        out.push_str(line);
+        blank_run = 0;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::{
+        EncryptionState, EncryptionVersion, Permissions, Stream, StringFormat,
+        content::{Content, Operation},
+        dictionary,
+    };
+
+    use super::*;
+
+    /// Builds a PDF with one Helvetica text line per page; `None` pages are blank.
+    fn build_pdf(pages: &[Option<&str>], title: Option<&str>) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+
+        let mut kids = Vec::new();
+        for text in pages {
+            let operations = match text {
+                Some(text) => vec![
+                    Operation::new("BT", vec![]),
+                    Operation::new("Tf", vec!["F1".into(), 24.into()]),
+                    Operation::new("Td", vec![72.into(), 700.into()]),
+                    Operation::new("Tj", vec![Object::string_literal(*text)]),
+                    Operation::new("ET", vec![]),
+                ],
+                None => vec![],
+            };
+            let content = Content { operations };
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+            let page_id = doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+            });
+            kids.push(page_id.into());
+        }
+
+        let count = i64::try_from(kids.len()).unwrap();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => count,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+
+        if let Some(title) = title {
+            let info_id = doc.add_object(dictionary! {
+                "Title" => Object::String(title.as_bytes().to_vec(), StringFormat::Literal),
+            });
+            doc.trailer.set("Info", info_id);
+        }
+
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Encrypts a PDF with RC4 and a 128-bit key, so that it opens with
+    /// `user_password`.
+    fn encrypt_pdf(bytes: &[u8], user_password: &str) -> Vec<u8> {
+        let mut doc = Document::load_mem(bytes).unwrap();
+        let id = Object::String((1..=16).collect(), StringFormat::Literal);
+        doc.trailer.set("ID", vec![id.clone(), id]);
+
+        let state = EncryptionState::try_from(EncryptionVersion::V2 {
+            document: &doc,
+            owner_password: "owner",
+            user_password,
+            key_length: 128,
+            permissions: Permissions::all(),
+        })
+        .unwrap();
+        doc.encrypt(&state).unwrap();
+
+        let mut encrypted = Vec::new();
+        doc.save_to(&mut encrypted).unwrap();
+        encrypted
+    }
+
+    #[test]
+    fn extracts_pages_with_offsets() {
+        let pdf = build_pdf(
+            &[Some("Hello page one"), Some("Second page here")],
+            Some("Doc"),
+        );
+        let text = extract_pdf(&pdf).unwrap();
+
+        assert_eq!(text.title.as_deref(), Some("Doc"));
+        assert_eq!(text.pages.len(), 2);
+
+        let observation = text.into_observation(None).unwrap();
+        let content = observation.content();
+        let map = observation.page_map().unwrap();
+
+        assert_eq!(observation.source_kind(), SourceKind::Pdf);
+        for (span, expected) in map
+            .spans()
+            .iter()
+            .zip(["Hello page one", "Second page here"])
+        {
+            assert_eq!(content[span.start_offset..span.end_offset].trim(), expected);
+        }
+
+        let second = content.find("Second").unwrap();
+        assert_eq!(map.page_at(second), Some(2));
+        assert_eq!(map.pages_for_range(0, content.len()), Some((1, 2)));
+    }
+
+    #[test]
+    fn blank_pages_keep_their_number() {
+        let pdf = build_pdf(&[None, Some("Only text")], None);
+        let observation = extract_pdf(&pdf).unwrap().into_observation(None).unwrap();
+        let map = observation.page_map().unwrap();
+
+        assert_eq!(map.spans().len(), 2);
+        assert_eq!(
+            map.page_at(observation.content().find("Only").unwrap()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn image_only_pdf_is_a_typed_error() {
+        let pdf = build_pdf(&[None, None], None);
+        assert!(matches!(extract_pdf(&pdf), Err(IngestError::PdfNoText)));
+    }
+
+    #[test]
+    fn reads_pdfs_encrypted_with_an_empty_password() {
+        let pdf = encrypt_pdf(&build_pdf(&[Some("Hidden in plain sight")], None), "");
+        let text = extract_pdf(&pdf).unwrap();
+
+        assert_eq!(text.pages, ["Hidden in plain sight"]);
+    }
+
+    #[test]
+    fn password_protected_pdf_is_a_typed_error() {
+        let pdf = encrypt_pdf(&build_pdf(&[Some("Secret")], None), "hunter2");
+        assert!(matches!(extract_pdf(&pdf), Err(IngestError::PdfEncrypted)));
+    }
+
+    #[test]
+    fn garbage_is_a_pdf_error() {
+        assert!(matches!(
+            extract_pdf(b"not a pdf"),
+            Err(IngestError::Pdf(_))
+        ));
+    }
+
+    #[test]
+    fn normalizes_layout_whitespace() {
+        assert_eq!(normalize_page("\n\n  a   \nb\n\n\n\nc  \n"), "  a\nb\n\nc");
+    }
+}
@@ -0,0 +1,548 @@
+use std::collections::{HashMap, HashSet};
+
+use domain::observation::{Observation, SourceKind};
+use ego_tree::NodeId;
+use reqwest::header::CONTENT_TYPE;
+use scraper::{ElementRef, Html, Selector, node::Element};
+use url::Url;
+
+use crate::{
+    USER_AGENT,
+    error::{IngestError, Result},
+    html,
+};
+
+/// Elements that never hold article text. `header` and `aside` are only
+/// chrome outside `<article>`/`<main>`; inside, they carry the headline,
+/// standfirst or pull quotes.
+const BOILERPLATE_TAGS: &[&str] = &[
+    "nav", "footer", "header", "aside", "form", "button", "iframe", "svg", "script", "style",
+    "noscript", "template", "dialog",
+];
+
+/// `class`/`id` tokens that mark navigation, ads and other page chrome.
+const BOILERPLATE_HINTS: &[&str] = &[
+    "ad",
+    "ads",
+    "advert",
+    "advertisement",
+    "banner",
+    "breadcrumb",
+    "breadcrumbs",
+    "comment",
+    "comments",
+    "cookie",
+    "footer",
+    "menu",
+    "nav",
+    "navbar",
+    "newsletter",
+    "popup",
+    "promo",
+    "related",
+    "share",
+    "sidebar",
+    "social",
+    "sponsor",
+    "sponsored",
+    "subscribe",
+];
+
+/// Tags kept when sanitizing the extracted article before converting it to text.
+const ARTICLE_TAGS: &[&str] = &[
+    "a",
+    "article",
+    "b",
+    "blockquote",
+    "br",
+    "code",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "i",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "span",
+    "strong",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+/// Minimum text length for an `<article>`/`<main>` element to be trusted as
+/// the article body without scoring.
+const MIN_LANDMARK_CHARS: usize = 200;
+
+/// Minimum text length for a paragraph to contribute to its container's score.
+const MIN_PARAGRAPH_CHARS: usize = 25;
+
+/// Factor applied to the score of a container whose `class`/`id` carries a
+/// [`BOILERPLATE_HINTS`] token.
+const HINT_PENALTY: f64 = 0.2;
+
+/// The readable part of a web page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Article {
+    pub title: Option<String>,
+    pub canonical_url: String,
+    /// The page's `<html lang>` attribute.
+    pub language: Option<String>,
+    pub text: String,
+}
+
+impl Article {
+    pub fn into_observation(self) -> Result<Observation> {
+        let mut builder = Observation::builder()
+            .content(self.text)
+            .source_kind(SourceKind::Web)
+            .source_url(self.canonical_url);
+
+        if let Some(title) = self.title {
+            builder = builder.title(title);
+        }
+
+        if let Some(language) = self.language {
+            builder = builder.language(language);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+pub struct WebIngester {
+    client: reqwest::Client,
+}
+
+impl WebIngester {
+    pub fn new() -> Result<Self> {
+        let client = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
+        Ok(Self::with_client(client))
+    }
+
+    #[must_use]
+    pub const fn with_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    /// Fetches an HTML page and extracts its main article.
+    pub async fn fetch(&self, url: &str) -> Result<Article> {
+        let response = self.client.get(url).send().await?.error_for_status()?;
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("text/html")
+            .to_ascii_lowercase();
+        if !content_type.contains("html") {
+            return Err(IngestError::UnsupportedContentType(content_type));
+        }
+
+        let final_url = response.url().to_string();
+        let body = response.text().await?;
+        extract_article(&body, &final_url)
+    }
+
+    pub async fn ingest(&self, url: &str) -> Result<Observation> {
+        self.fetch(url).await?.into_observation()
+    }
+}
+
+/// Extracts the title, canonical URL and sanitized main-content text of an
+/// HTML document, dropping navigation, footers, ads and other boilerplate.
+pub fn extract_article(document: &str, page_url: &str) -> Result<Article> {
+    let mut doc = Html::parse_document(document);
+
+    let title = select_first(&doc, "title")
+        .map(html::element_to_text)
+        .filter(|t| !t.is_empty());
+    let canonical_url = canonical_url(&doc, page_url);
+    let language = select_first(&doc, "html[lang]")
+        .and_then(|el| el.value().attr("lang"))
+        .map(str::to_string);
+
+    strip_boilerplate(&mut doc);
+    let content = main_content(&doc).map(|el| el.id());
+    strip_hinted(&mut doc, content);
+
+    let content = content
+        .and_then(|id| doc.tree.get(id))
+        .and_then(ElementRef::wrap)
+        .map_or_else(String::new, |el| el.html());
+    let text = sanitize_to_text(&content);
+
+    if text.is_empty() {
+        return Err(IngestError::NoReadableContent(page_url.to_string()));
+    }
+
+    Ok(Article {
+        title,
+        canonical_url,
+        language,
+        text,
+    })
+}
+
+fn select_first<'a>(doc: &'a Html, selector: &str) -> Option<ElementRef<'a>> {
+    let selector = Selector::parse(selector).expect("static selector");
+    doc.select(&selector).next()
+}
+
+/// Resolves `<link rel="canonical">` against the page URL, falling back to
+/// the page URL itself.
+fn canonical_url(doc: &Html, page_url: &str) -> String {
+    let href = select_first(doc, r#"link[rel~="canonical"]"#)
+        .and_then(|el| el.value().attr("href"))
+        .map(str::trim)
+        .filter(|href| !href.is_empty());
+
+    let Some(href) = href else {
+        return page_url.to_string();
+    };
+
+    Url::parse(page_url)
+        .and_then(|base| base.join(href))
+        .map_or_else(|_| href.to_string(), |url| url.to_string())
+}
+
+/// Whether an element is page chrome by its tag or ARIA role alone.
+fn is_boilerplate(element: ElementRef<'_>) -> bool {
+    let name = element.value().name();
+    if BOILERPLATE_TAGS.contains(&name)
+        && !(matches!(name, "header" | "aside") && is_in_landmark(element))
+    {
+        return true;
+    }
+
+    let element = element.value();
+
+    element.attr("role").is_some_and(|role| {
+        matches!(
+            role,
+            "navigation" | "banner" | "contentinfo" | "complementary"
+        )
+    }) || element.attr("aria-hidden") == Some("true")
+}
+
+/// Whether an element's `class` or `id` looks like ads, comments, sidebars
+/// or other chrome. Such names are only a hint: `content comments-wrapper`
+/// can hold the article itself.
+fn has_boilerplate_hint(element: &Element) -> bool {
+    element
+        .attr("class")
+        .into_iter()
+        .chain(element.attr("id"))
+        .flat_map(|value| value.split(|c: char| !c.is_ascii_alphanumeric()))
+        .map(str::to_ascii_lowercase)
+        .any(|token| BOILERPLATE_HINTS.contains(&token.as_str()))
+}
+
+fn is_in_landmark(element: ElementRef<'_>) -> bool {
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|el| matches!(el.value().name(), "article" | "main"))
+}
+
+fn has_hinted_ancestor(element: ElementRef<'_>) -> bool {
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|el| has_boilerplate_hint(el.value()))
+}
+
+fn strip_boilerplate(doc: &mut Html) {
+    detach_where(doc, is_boilerplate);
+}
+
+/// Drops hinted elements, except the main content and its ancestors.
+fn strip_hinted(doc: &mut Html, content: Option<NodeId>) {
+    let keep: HashSet<NodeId> = content
+        .and_then(|id| doc.tree.get(id))
+        .into_iter()
+        .flat_map(|node| std::iter::once(node).chain(node.ancestors()))
+        .map(|node| node.id())
+        .collect();
+
+    detach_where(doc, |el| {
+        !keep.contains(&el.id()) && has_boilerplate_hint(el.value())
+    });
+}
+
+/// Detaches every element under `<body>` matching `doomed`, except
+/// `<article>` and `<main>`.
+fn detach_where(doc: &mut Html, doomed: impl Fn(ElementRef<'_>) -> bool) {
+    let body = Selector::parse("body *").expect("static selector");
+    let doomed: Vec<_> = doc
+        .select(&body)
+        .filter(|el| !matches!(el.value().name(), "article" | "main"))
+        .filter(|el| doomed(*el))
+        .map(|el| el.id())
+        .collect();
+
+    for id in doomed {
+        if let Some(mut node) = doc.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+}
+
+fn text_len(element: ElementRef<'_>) -> usize {
+    element.text().map(|t| t.trim().chars().count()).sum()
+}
+
+/// Share of an element's text that sits inside links.
+fn link_density(element: ElementRef<'_>) -> f64 {
+    let total = text_len(element);
+    if total == 0 {
+        return 1.0;
+    }
+
+    let links = Selector::parse("a").expect("static selector");
+    let linked: usize = element.select(&links).map(text_len).sum();
+    linked as f64 / total as f64
+}
+
+/// Picks the element most likely to hold the article body: a substantial
+/// `<article>`/`<main>` landmark if there is one, otherwise the container
+/// whose paragraphs score highest. Landmarks and containers that look like
+/// chrome only win when nothing better is around.
+fn main_content(doc: &Html) -> Option<ElementRef<'_>> {
+    for landmark in ["article", "main", r#"[role="main"]"#] {
+        let selector = Selector::parse(landmark).expect("static selector");
+        let mut landmarks = doc
+            .select(&selector)
+            .filter(|el| text_len(*el) >= MIN_LANDMARK_CHARS)
+            .peekable();
+        let first = landmarks.peek().copied();
+        if let Some(el) = landmarks.find(|el| !has_hinted_ancestor(*el)).or(first) {
+            return Some(el);
+        }
+    }
+
+    let paragraphs = Selector::parse("p, pre, blockquote, td").expect("static selector");
+    let mut scores: HashMap<_, f64> = HashMap::new();
+
+    for paragraph in doc.select(&paragraphs) {
+        let len = text_len(paragraph);
+        if len < MIN_PARAGRAPH_CHARS {
+            continue;
+        }
+
+        let commas = paragraph
+            .text()
+            .map(|t| t.matches(',').count())
+            .sum::<usize>();
+        let score = 1.0 + commas as f64 + (len as f64 / 100.0).min(3.0);
+
+        let parent = paragraph.parent().and_then(ElementRef::wrap);
+        if let Some(parent) = parent {
+            *scores.entry(parent.id()).or_default() += score;
+            if let Some(grandparent) = parent.parent().and_then(ElementRef::wrap) {
+                *scores.entry(grandparent.id()).or_default() += score / 2.0;
+            }
+        }
+    }
+
+    let best = scores
+        .into_iter()
+        .filter_map(|(id, score)| {
+            let el = doc.tree.get(id).and_then(ElementRef::wrap)?;
+            let penalty = if has_boilerplate_hint(el.value()) {
+                HINT_PENALTY
+            } else {
+                1.0
+            };
+            Some((el, score * penalty * (1.0 - link_density(el))))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(el, _)| el);
+
+    best.or_else(|| select_first(doc, "body"))
+}
+
+/// Runs the extracted HTML through ammonia, keeping only structural tags,
+/// then flattens it to plain text.
+fn sanitize_to_text(content: &str) -> String {
+    let tags: HashSet<&str> = ARTICLE_TAGS.iter().copied().collect();
+    let clean = ammonia::Builder::default()
+        .tags(tags)
+        .clean(content)
+        .to_string();
+    html::fragment_to_text(&clean)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!doctype html>
+<html lang="en-GB">
+<head>
+  <title> Crabs and their habits </title>
+  <link rel="canonical" href="/articles/crabs">
+</head>
+<body>
+  <header><a href="/">Home</a> <a href="/about">About</a></header>
+  <nav class="site-nav"><ul><li><a href="/a">Section A</a></li></ul></nav>
+  <div class="layout">
+    <div class="sidebar">Popular: <a href="/x">Something else entirely</a></div>
+    <div id="content">
+      <h1>Crabs and their habits</h1>
+      <p>Crabs walk sideways, which surprises people who see it for the first time.</p>
+      <div class="ad-slot ad">Buy crab repellent today, limited offer, act now!</div>
+      <p>They moult their shells, hide under rocks, and scavenge along the shore at night.</p>
+      <script>track("pageview")</script>
+    </div>
+  </div>
+  <footer>Copyright Crab Corp, all rights reserved, forever and ever.</footer>
+</body>
+</html>"#;
+
+    #[test]
+    fn extracts_main_content_without_boilerplate() {
+        let article = extract_article(PAGE, "https://example.com/page?utm=1").unwrap();
+
+        assert_eq!(article.title.as_deref(), Some("Crabs and their habits"));
+        assert_eq!(article.canonical_url, "https://example.com/articles/crabs");
+        assert!(article.text.contains("Crabs walk sideways"));
+        assert!(article.text.contains("scavenge along the shore"));
+
+        for boilerplate in [
+            "Home",
+            "Section A",
+            "Popular",
+            "repellent",
+            "Copyright",
+            "track(",
+        ] {
+            assert!(
+                !article.text.contains(boilerplate),
+                "found {boilerplate:?} in {:?}",
+                article.text
+            );
+        }
+    }
+
+    #[test]
+    fn prefers_article_landmark() {
+        let body = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(5);
+        let page = format!(
+            "<html><body><div><p>Teaser text that is long enough, with commas, to score.</p></div>\
+             <article><p>{body}</p></article></body></html>"
+        );
+
+        let article = extract_article(&page, "https://example.com/").unwrap();
+        assert_eq!(article.text, body.trim());
+        assert_eq!(article.canonical_url, "https://example.com/");
+        assert!(article.title.is_none());
+    }
+
+    #[test]
+    fn keeps_content_inside_hinted_wrappers() {
+        let page = r#"<html><body>
+  <div class="post-comments-wrapper content">
+    <div class="entry">
+      <p>Crabs walk sideways, which surprises people who see it for the first time.</p>
+      <p>They moult their shells, hide under rocks, and scavenge along the shore at night.</p>
+      <div class="share">Share this on every network</div>
+    </div>
+    <div class="comments">
+      <div class="comment"><p>Great article, thanks, loved it, more please, and more, and more!</p></div>
+    </div>
+  </div>
+</body></html>"#;
+
+        let article = extract_article(page, "https://example.com/").unwrap();
+        assert!(article.text.contains("Crabs walk sideways"));
+        assert!(article.text.contains("scavenge along the shore"));
+        assert!(!article.text.contains("Share this"));
+        assert!(!article.text.contains("Great article"));
+    }
+
+    #[test]
+    fn prefers_landmarks_outside_hinted_elements() {
+        let teaser = "Read this other story, with commas, about lobsters instead. ".repeat(5);
+        let body = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(5);
+
+        let page = format!(
+            "<html><body><div class=\"sidebar\"><article><p>{teaser}</p></article></div>\
+             <article><p>{body}</p></article></body></html>"
+        );
+        let article = extract_article(&page, "https://example.com/").unwrap();
+        assert_eq!(article.text, body.trim());
+
+        let page = format!(
+            "<html><body><section id=\"related-story\"><article><p>{body}</p></article>\
+             </section></body></html>"
+        );
+        let article = extract_article(&page, "https://example.com/").unwrap();
+        assert_eq!(article.text, body.trim());
+    }
+
+    #[test]
+    fn keeps_headers_inside_the_article() {
+        let page = r#"<html><body>
+  <header><a href="/">Crab News</a></header>
+  <article>
+    <header>
+      <h1>Crabs walk sideways</h1>
+      <p class="dek">Why the shore's most awkward walkers never go straight.</p>
+    </header>
+    <aside><p>Crabs have ten legs, counting the two claws at the front.</p></aside>
+    <header role="banner">Sign up for the newsletter</header>
+    <p>Crabs walk sideways, which surprises people who see it for the first time.
+       Their legs bend outwards, so a sideways step is the longest one they can take,
+       and they scavenge along the shore at night.</p>
+  </article>
+</body></html>"#;
+
+        let article = extract_article(page, "https://example.com/").unwrap();
+        assert!(article.text.starts_with("Crabs walk sideways\n"));
+        assert!(article.text.contains("most awkward walkers"));
+        assert!(article.text.contains("ten legs"));
+        assert!(!article.text.contains("Crab News"));
+        assert!(!article.text.contains("newsletter"));
+    }
+
+    #[test]
+    fn builds_web_observation() {
+        let observation = extract_article(PAGE, "https://example.com/page")
+            .unwrap()
+            .into_observation()
+            .unwrap();
+
+        assert_eq!(observation.source_kind(), SourceKind::Web);
+        assert_eq!(
+            observation.source_url(),
+            Some("https://example.com/articles/crabs")
+        );
+        assert_eq!(observation.language(), Some("en-GB"));
+    }
+
+    #[test]
+    fn empty_page_is_an_error() {
+        let result = extract_article(
+            "<html><body><nav>Only nav</nav></body></html>",
+            "https://e.com",
+        );
+        assert!(matches!(result, Err(IngestError::NoReadableContent(_))));
+    }
+}
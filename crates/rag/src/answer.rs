@@ -0,0 +1,298 @@
+use std::{fmt::Write, sync::Arc};
+
+use domain::{
+    ids::{ChunkId, ObservationId},
+    search::SearchQuery,
+};
+
+use crate::{
+    error::Result,
+    llm::{ChatRequest, LlmClient, Message},
+    retriever::{HybridRetriever, RetrievedChunk},
+};
+
+const SYSTEM_PROMPT: &str = "You answer questions using only the numbered sources \
+you are given. Cite the sources that support each statement by number in square \
+brackets, like [1] or [2][3]. If the sources do not contain the answer, say so \
+instead of guessing.";
+
+/// Answer given when retrieval finds nothing to base an answer on.
+const NO_SOURCES_ANSWER: &str = "No sources matched the question.";
+
+/// How retrieved chunks are turned into a prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnswerConfig {
+    /// Most chunk tokens packed into the prompt, counted with each chunk's
+    /// stored token estimate.
+    pub context_tokens: usize,
+    pub system_prompt: String,
+}
+
+impl Default for AnswerConfig {
+    fn default() -> Self {
+        Self {
+            context_tokens: 3000,
+            system_prompt: SYSTEM_PROMPT.to_string(),
+        }
+    }
+}
+
+impl AnswerConfig {
+    #[must_use]
+    pub const fn with_context_tokens(mut self, context_tokens: usize) -> Self {
+        self.context_tokens = context_tokens;
+        self
+    }
+
+    #[must_use]
+    pub fn with_system_prompt(mut self, system_prompt: impl Into<String>) -> Self {
+        self.system_prompt = system_prompt.into();
+        self
+    }
+}
+
+/// A source the answer cites, by the number it had in the prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Citation {
+    pub number: usize,
+    pub chunk_id: ChunkId,
+    pub observation_id: ObservationId,
+    pub title: Option<String>,
+    pub source_url: Option<String>,
+    /// Byte range of the chunk in the observation's content.
+    pub start_offset: usize,
+    pub end_offset: usize,
+}
+
+impl Citation {
+    fn new(number: usize, source: &RetrievedChunk) -> Self {
+        Self {
+            number,
+            chunk_id: source.chunk.id(),
+            observation_id: source.observation.id,
+            title: source.observation.title.clone(),
+            source_url: source.observation.source_url.clone(),
+            start_offset: source.chunk.start_offset(),
+            end_offset: source.chunk.end_offset(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Answer {
+    pub text: String,
+    /// The sources the text cites, in order of first citation. Citations
+    /// of numbers that were not in the prompt are left out.
+    pub citations: Vec<Citation>,
+}
+
+/// Answers questions from retrieved chunks with a chat model.
+pub struct Answerer {
+    retriever: HybridRetriever,
+    llm: Arc<dyn LlmClient>,
+    config: AnswerConfig,
+}
+
+impl Answerer {
+    pub fn new(retriever: HybridRetriever, llm: Arc<dyn LlmClient>) -> Self {
+        Self {
+            retriever,
+            llm,
+            config: AnswerConfig::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_config(mut self, config: AnswerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Retrieves chunks for the question in `query.text`, asks the model to
+    /// answer from them and resolves the answer's citations.
+    pub async fn answer(&self, query: &SearchQuery) -> Result<Answer> {
+        let retrieved = self.retriever.retrieve(query).await?;
+        let sources = pack(retrieved, self.config.context_tokens);
+        if sources.is_empty() {
+            return Ok(Answer {
+                text: NO_SOURCES_ANSWER.to_string(),
+                citations: Vec::new(),
+            });
+        }
+
+        let request = ChatRequest::new(vec![
+            Message::system(&self.config.system_prompt),
+            Message::user(prompt(&query.text, &sources)),
+        ]);
+        let text = self.llm.complete(&request).await?;
+
+        let citations = cited_numbers(&text)
+            .into_iter()
+            .filter_map(|n| Some(Citation::new(n, sources.get(n.checked_sub(1)?)?)))
+            .collect();
+
+        Ok(Answer { text, citations })
+    }
+}
+
+/// The best-ranked chunks whose token estimates fit in `budget` together.
+/// A chunk too large for what is left is skipped in favour of smaller ones
+/// ranked below it.
+fn pack(retrieved: Vec<RetrievedChunk>, budget: usize) -> Vec<RetrievedChunk> {
+    let mut used = 0usize;
+    retrieved
+        .into_iter()
+        .filter(|r| {
+            let tokens = r.chunk.token_estimate() as usize;
+            let fits = used + tokens <= budget;
+            if fits {
+                used += tokens;
+            }
+            fits
+        })
+        .collect()
+}
+
+fn prompt(question: &str, sources: &[RetrievedChunk]) -> String {
+    let mut prompt = String::from("Sources:\n");
+    for (i, source) in sources.iter().enumerate() {
+        let title = source.observation.title.as_deref().unwrap_or("Untitled");
+        let _ = write!(prompt, "\n[{}] {title}", i + 1);
+        if let Some(url) = &source.observation.source_url {
+            let _ = write!(prompt, " ({url})");
+        }
+        let _ = writeln!(prompt, "\n{}", source.chunk.text().trim());
+    }
+    let _ = write!(prompt, "\nQuestion: {}", question.trim());
+    prompt
+}
+
+/// Source numbers cited in `text` as `[1]`, `[2][3]` or `[2, 3]`, without
+/// repeats, in order of first citation.
+fn cited_numbers(text: &str) -> Vec<usize> {
+    let mut numbers = Vec::new();
+    let mut rest = text;
+
+    while let Some(open) = rest.find('[') {
+        rest = &rest[open + 1..];
+        let Some(close) = rest.find(']') else {
+            break;
+        };
+        let inside = &rest[..close];
+        let parsed: Option<Vec<usize>> = inside.split(',').map(|n| n.trim().parse().ok()).collect();
+        if let Some(parsed) = parsed {
+            rest = &rest[close + 1..];
+            for n in parsed {
+                if !numbers.contains(&n) {
+                    numbers.push(n);
+                }
+            }
+        }
+    }
+
+    numbers
+}
+
+#[cfg(test)]
+mod tests {
+    use domain::ids::ObservationId;
+
+    use super::*;
+    use crate::{
+        llm::FakeLlm,
+        retriever::RetrieverConfig,
+        testing::{FakeIndex, LengthEmbedder, hit},
+    };
+
+    fn answerer(index: FakeIndex, llm: Arc<FakeLlm>, config: AnswerConfig) -> Answerer {
+        let retriever = HybridRetriever::new(Arc::new(index), Arc::new(LengthEmbedder))
+            .with_config(RetrieverConfig::default().with_weights(1.0, 0.0));
+        Answerer::new(retriever, llm).with_config(config)
+    }
+
+    #[test]
+    fn finds_cited_numbers() {
+        assert_eq!(
+            cited_numbers("Crabs walk [2]. They moult [1][2] and hide [3, 1]. See [note] [x, 4]."),
+            [2, 1, 3]
+        );
+        assert!(cited_numbers("no citations [").is_empty());
+    }
+
+    #[tokio::test]
+    async fn answers_with_citations_to_chunks() {
+        let (a, b) = (ObservationId::new(), ObservationId::new());
+        let mut walking = hit(a, "Crabs walk sideways.", 10, 1.0);
+        walking.observation.title = Some("Crab gaits".to_string());
+        walking.observation.source_url = Some("https://example.com/gaits".to_string());
+        let moulting = hit(b, "Crabs moult their shells.", 0, 0.5);
+
+        let llm = Arc::new(FakeLlm::new(
+            "Crabs walk sideways [1] and moult [2]. Also [7].",
+        ));
+        let index = FakeIndex::new(vec![walking.clone(), moulting.clone()], Vec::new());
+        let answer = answerer(index, llm.clone(), AnswerConfig::default())
+            .answer(&SearchQuery::new("How do crabs move?", 5))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            answer.text,
+            "Crabs walk sideways [1] and moult [2]. Also [7]."
+        );
+        assert_eq!(answer.citations.len(), 2);
+        assert_eq!(
+            answer.citations[0],
+            Citation {
+                number: 1,
+                chunk_id: walking.chunk.id(),
+                observation_id: a,
+                title: Some("Crab gaits".to_string()),
+                source_url: Some("https://example.com/gaits".to_string()),
+                start_offset: 10,
+                end_offset: 30,
+            }
+        );
+        assert_eq!(answer.citations[1].chunk_id, moulting.chunk.id());
+
+        let requests = llm.requests();
+        let user = &requests[0].messages[1].content;
+        assert!(user.contains("[1] Crab gaits (https://example.com/gaits)\nCrabs walk sideways."));
+        assert!(user.contains("[2] Untitled\nCrabs moult their shells."));
+        assert!(user.ends_with("Question: How do crabs move?"));
+    }
+
+    #[tokio::test]
+    async fn packs_only_what_fits_the_budget() {
+        let id = ObservationId::new();
+        let hits = vec![hit(id, "first", 0, 1.0), hit(id, "second", 100, 1.0)];
+        let llm = Arc::new(FakeLlm::new("[2]"));
+
+        // Each test chunk is estimated at one token.
+        let config = AnswerConfig::default().with_context_tokens(1);
+        let answer = answerer(FakeIndex::new(hits, Vec::new()), llm.clone(), config)
+            .answer(&SearchQuery::new("q", 5))
+            .await
+            .unwrap();
+
+        assert!(answer.citations.is_empty());
+        let requests = llm.requests();
+        assert!(!requests[0].messages[1].content.contains("second"));
+    }
+
+    #[tokio::test]
+    async fn does_not_call_the_model_without_sources() {
+        let llm = Arc::new(FakeLlm::new(""));
+        let answer = answerer(
+            FakeIndex::new(Vec::new(), Vec::new()),
+            llm.clone(),
+            AnswerConfig::default(),
+        )
+        .answer(&SearchQuery::new("q", 5))
+        .await
+        .unwrap();
+
+        assert_eq!(answer.text, NO_SOURCES_ANSWER);
+        assert!(llm.requests().is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, RagError>;
+
+#[derive(Debug, Error)]
+pub enum RagError {
+    #[error(transparent)]
+    Store(#[from] store::StoreError),
+
+    #[error(transparent)]
+    Embed(#[from] embedding::EmbedError),
+
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+
+    #[error(transparent)]
+    Rerank(#[from] RerankError),
+}
+
+#[derive(Debug, Error)]
+pub enum LlmError {
+    #[error("LLM transport failed: {0}")]
+    Transport(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("LLM provider returned {status}: {message}")]
+    Provider { status: u16, message: String },
+
+    #[error("LLM returned no completion")]
+    EmptyCompletion,
+
+    #[error("LLM sent an invalid stream event: {0}")]
+    InvalidStream(String),
+}
+
+#[derive(Debug, Error)]
+pub enum RerankError {
+    #[error("rerank transport failed: {0}")]
+    Transport(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("rerank provider returned {status}: {message}")]
+    Provider { status: u16, message: String },
+
+    #[error("local rerank model failed: {0}")]
+    Model(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("expected {expected} rerank scores, got {actual}")]
+    CountMismatch { expected: usize, actual: usize },
+}
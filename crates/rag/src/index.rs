@@ -0,0 +1,69 @@
+use async_trait::async_trait;
+use domain::{
+    ids::ChunkId,
+    search::{SearchFilters, SearchHit},
+};
+use store::PgStore;
+
+use crate::error::Result;
+
+/// Where retrieval finds chunks: a full-text index and a vector index over
+/// the same chunks.
+#[async_trait]
+pub trait ChunkIndex: Send + Sync {
+    /// The `k` best full-text matches for `query`, best first.
+    async fn keyword_search(
+        &self,
+        query: &str,
+        k: usize,
+        filters: &SearchFilters,
+    ) -> Result<Vec<SearchHit>>;
+
+    /// The `k` chunks whose `model` embeddings are nearest `vector`, best
+    /// first.
+    async fn vector_search(
+        &self,
+        model: &str,
+        vector: &[f32],
+        k: usize,
+        filters: &SearchFilters,
+    ) -> Result<Vec<SearchHit>>;
+
+    /// The `model` embeddings of the given chunks, leaving out chunks that
+    /// have none.
+    async fn embeddings(
+        &self,
+        model: &str,
+        chunk_ids: &[ChunkId],
+    ) -> Result<Vec<(ChunkId, Vec<f32>)>>;
+}
+
+#[async_trait]
+impl ChunkIndex for PgStore {
+    async fn keyword_search(
+        &self,
+        query: &str,
+        k: usize,
+        filters: &SearchFilters,
+    ) -> Result<Vec<SearchHit>> {
+        Ok(Self::keyword_search(self, query, k, filters).await?)
+    }
+
+    async fn vector_search(
+        &self,
+        model: &str,
+        vector: &[f32],
+        k: usize,
+        filters: &SearchFilters,
+    ) -> Result<Vec<SearchHit>> {
+        Ok(self.search_similar(model, vector, k, filters).await?)
+    }
+
+    async fn embeddings(
+        &self,
+        model: &str,
+        chunk_ids: &[ChunkId],
+    ) -> Result<Vec<(ChunkId, Vec<f32>)>> {
+        Ok(Self::embeddings(self, model, chunk_ids).await?)
+    }
+}
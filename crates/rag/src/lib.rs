@@ -1,14 +1,20 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+pub mod answer;
+pub mod error;
+pub mod index;
+pub mod llm;
+pub mod rerank;
+pub mod retriever;
 
 #[cfg(test)]
-mod tests {
-    use super::*;
+mod testing;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use crate::{
+    answer::{Answer, AnswerConfig, Answerer, Citation},
+    error::{LlmError, RagError, RerankError, Result},
+    index::ChunkIndex,
+    llm::{
+        ChatRequest, FakeLlm, LlmClient, Message, OpenAiChat, OpenAiChatConfig, Role, TokenStream,
+    },
+    rerank::{CrossEncoder, HttpReranker, HttpRerankerConfig, RerankApi, Reranker},
+    retriever::{HybridRetriever, RetrievedChunk, RetrieverConfig, SignalScore},
+};
@@ -0,0 +1,108 @@
+mod fake;
+mod openai;
+
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream};
+use serde::Serialize;
+
+pub use self::{
+    fake::FakeLlm,
+    openai::{OpenAiChat, OpenAiChatConfig},
+};
+use crate::error::LlmError;
+
+/// Pieces of a reply in the order the model produces them.
+pub type TokenStream = BoxStream<'static, Result<String, LlmError>>;
+
+/// Who a chat message is from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Message {
+    pub role: Role,
+    pub content: String,
+}
+
+impl Message {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::System,
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::Assistant,
+            content: content.into(),
+        }
+    }
+}
+
+/// A conversation to continue, with the sampling settings to use.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatRequest {
+    pub messages: Vec<Message>,
+    /// Sampling temperature; the server's default when unset.
+    pub temperature: Option<f32>,
+    /// Most tokens the reply may have; the server's default when unset.
+    pub max_tokens: Option<u32>,
+    /// Sequences that end the reply where they would first appear. They
+    /// are not part of the reply.
+    pub stop: Vec<String>,
+}
+
+impl ChatRequest {
+    #[must_use]
+    pub fn new(messages: Vec<Message>) -> Self {
+        Self {
+            messages,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub const fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    #[must_use]
+    pub fn with_stop(mut self, stop: impl Into<String>) -> Self {
+        self.stop.push(stop.into());
+        self
+    }
+}
+
+/// A chat model that continues a conversation with one assistant message.
+#[async_trait]
+pub trait LlmClient: Send + Sync {
+    /// Returns the content of the model's reply to `request`.
+    async fn complete(&self, request: &ChatRequest) -> Result<String, LlmError>;
+
+    /// Returns the model's reply to `request` as it is generated. Clients
+    /// that cannot stream yield the whole reply as one piece.
+    async fn stream(&self, request: &ChatRequest) -> Result<TokenStream, LlmError> {
+        let reply = self.complete(request).await?;
+        Ok(Box::pin(stream::once(async { Ok(reply) })))
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::{Mutex, PoisonError};
+
+use async_trait::async_trait;
+use futures::stream;
+
+use super::{ChatRequest, LlmClient, TokenStream};
+use crate::error::LlmError;
+
+/// A deterministic chat model for tests.
+///
+/// It replies with a fixed text, cut at the request's first stop sequence
+/// and to its token limit, counting whitespace-separated words as tokens.
+/// Streaming yields the reply word by word, each word with the whitespace
+/// that follows it. Every request is recorded.
+#[derive(Debug, Default)]
+pub struct FakeLlm {
+    reply: String,
+    requests: Mutex<Vec<ChatRequest>>,
+}
+
+impl FakeLlm {
+    pub fn new(reply: impl Into<String>) -> Self {
+        Self {
+            reply: reply.into(),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<ChatRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn reply_to(&self, request: &ChatRequest) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(request.clone());
+
+        let end = request
+            .stop
+            .iter()
+            .filter(|stop| !stop.is_empty())
+            .filter_map(|stop| self.reply.find(stop.as_str()))
+            .min()
+            .unwrap_or(self.reply.len());
+        let limit = request.max_tokens.map_or(usize::MAX, |n| n as usize);
+
+        self.reply[..end]
+            .split_inclusive(char::is_whitespace)
+            .fold(Vec::<String>::new(), |mut tokens, piece| {
+                // Runs of whitespace stay with the word before them.
+                match tokens.last_mut() {
+                    Some(last) if piece.trim().is_empty() => last.push_str(piece),
+                    _ => tokens.push(piece.to_string()),
+                }
+                tokens
+            })
+            .into_iter()
+            .take(limit)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl LlmClient for FakeLlm {
+    async fn complete(&self, request: &ChatRequest) -> Result<String, LlmError> {
+        Ok(self.reply_to(request).concat())
+    }
+
+    async fn stream(&self, request: &ChatRequest) -> Result<TokenStream, LlmError> {
+        Ok(Box::pin(stream::iter(
+            self.reply_to(request).into_iter().map(Ok),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::llm::Message;
+
+    fn request() -> ChatRequest {
+        ChatRequest::new(vec![Message::user("hi")])
+    }
+
+    #[tokio::test]
+    async fn streams_the_reply_word_by_word() {
+        let llm = FakeLlm::new("Crabs  walk\nsideways.");
+        let tokens: Vec<String> = llm
+            .stream(&request())
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(tokens, ["Crabs  ", "walk\n", "sideways."]);
+        assert_eq!(
+            llm.complete(&request()).await.unwrap(),
+            "Crabs  walk\nsideways."
+        );
+        assert_eq!(llm.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn honours_stop_sequences_and_token_limits() {
+        let llm = FakeLlm::new("one two three. four five");
+
+        let stopped = request().with_stop("five").with_stop(".");
+        assert_eq!(llm.complete(&stopped).await.unwrap(), "one two three");
+
+        let limited = request().with_max_tokens(2);
+        assert_eq!(llm.complete(&limited).await.unwrap(), "one two ");
+    }
+}
@@ -0,0 +1,405 @@
+use std::{collections::VecDeque, time::Duration};
+
+use async_trait::async_trait;
+use embedding::openai::error_message;
+use futures::stream;
+use serde::{Deserialize, Serialize};
+
+use super::{ChatRequest, LlmClient, Message, TokenStream};
+use crate::error::LlmError;
+
+/// Connection settings for an [`OpenAiChat`].
+#[derive(Debug, Clone)]
+pub struct OpenAiChatConfig {
+    /// API root including the version prefix, e.g. `http://localhost:8000/v1`.
+    pub base_url: String,
+    pub model: String,
+    pub api_key: Option<String>,
+    /// Longest a whole completion may take.
+    pub timeout: Duration,
+}
+
+impl OpenAiChatConfig {
+    pub fn new(base_url: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            model: model.into(),
+            api_key: None,
+            timeout: Duration::from_secs(300),
+        }
+    }
+
+    #[must_use]
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[derive(Serialize)]
+struct CompletionRequest<'a> {
+    model: &'a str,
+    messages: &'a [Message],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    stop: &'a [String],
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct CompletionResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ChoiceMessage,
+}
+
+#[derive(Deserialize)]
+struct ChoiceMessage {
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StreamChunk {
+    choices: Vec<StreamChoice>,
+}
+
+#[derive(Deserialize)]
+struct StreamChoice {
+    delta: ChoiceMessage,
+}
+
+/// What one line of a server-sent event stream carries.
+#[derive(Debug, PartialEq, Eq)]
+enum StreamLine {
+    Token(String),
+    Done,
+    Ignored,
+}
+
+/// Reads a streamed completion response, which arrives in network chunks
+/// that need not line up with event boundaries.
+struct EventReader {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+    tokens: VecDeque<String>,
+    done: bool,
+}
+
+/// Chat client for servers speaking the OpenAI `/v1/chat/completions`
+/// protocol, such as OpenAI itself, vLLM, llama.cpp server and Ollama.
+pub struct OpenAiChat {
+    client: reqwest::Client,
+    config: OpenAiChatConfig,
+}
+
+impl OpenAiChat {
+    pub fn new(config: OpenAiChatConfig) -> Result<Self, LlmError> {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| LlmError::Transport(Box::new(e)))?;
+        Ok(Self::with_client(client, config))
+    }
+
+    #[must_use]
+    pub const fn with_client(client: reqwest::Client, config: OpenAiChatConfig) -> Self {
+        Self { client, config }
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> &OpenAiChatConfig {
+        &self.config
+    }
+}
+
+impl OpenAiChat {
+    /// Sends `request` and returns the response once it has a success
+    /// status.
+    async fn send(
+        &self,
+        request: &ChatRequest,
+        stream: bool,
+    ) -> Result<reqwest::Response, LlmError> {
+        let url = format!(
+            "{}/chat/completions",
+            self.config.base_url.trim_end_matches('/')
+        );
+        let body = CompletionRequest {
+            model: &self.config.model,
+            messages: &request.messages,
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            stop: &request.stop,
+            stream,
+        };
+
+        let mut http = self.client.post(url).json(&body);
+        if let Some(api_key) = &self.config.api_key {
+            http = http.bearer_auth(api_key);
+        }
+        let response = http
+            .send()
+            .await
+            .map_err(|e| LlmError::Transport(Box::new(e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(LlmError::Provider {
+                status: status.as_u16(),
+                message: error_message(&response.text().await.unwrap_or_default()),
+            });
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LlmClient for OpenAiChat {
+    async fn complete(&self, request: &ChatRequest) -> Result<String, LlmError> {
+        let response = self.send(request, false).await?;
+        let status = response.status();
+
+        let body: CompletionResponse = response.json().await.map_err(|e| LlmError::Provider {
+            status: status.as_u16(),
+            message: format!("invalid response body: {e}"),
+        })?;
+        body.choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or(LlmError::EmptyCompletion)
+    }
+
+    async fn stream(&self, request: &ChatRequest) -> Result<TokenStream, LlmError> {
+        let reader = EventReader {
+            response: self.send(request, true).await?,
+            buffer: Vec::new(),
+            tokens: VecDeque::new(),
+            done: false,
+        };
+        Ok(Box::pin(stream::unfold(reader, |mut reader| async move {
+            let next = reader.next_token().await.transpose()?;
+            Some((next, reader))
+        })))
+    }
+}
+
+impl EventReader {
+    /// The next non-empty token, or `None` once the stream has ended.
+    /// Errors end the stream too.
+    async fn next_token(&mut self) -> Result<Option<String>, LlmError> {
+        loop {
+            if let Some(token) = self.tokens.pop_front() {
+                return Ok(Some(token));
+            }
+            if self.done {
+                return Ok(None);
+            }
+
+            let chunk = self.response.chunk().await.map_err(|e| {
+                self.done = true;
+                LlmError::Transport(Box::new(e))
+            })?;
+            match chunk {
+                Some(bytes) => self.buffer.extend_from_slice(&bytes),
+                // A final line without a trailing newline still counts.
+                None if !self.buffer.is_empty() => self.buffer.push(b'\n'),
+                None => self.done = true,
+            }
+
+            while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                match parse_line(&String::from_utf8_lossy(&line)) {
+                    Ok(StreamLine::Token(token)) => self.tokens.push_back(token),
+                    Ok(StreamLine::Done) => {
+                        self.done = true;
+                        break;
+                    }
+                    Ok(StreamLine::Ignored) => {}
+                    Err(err) => {
+                        self.done = true;
+                        return Err(err);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Interprets one line of the event stream. Only `data:` lines matter;
+/// comments, other fields and blank separators are ignored.
+fn parse_line(line: &str) -> Result<StreamLine, LlmError> {
+    let Some(data) = line.trim_end_matches(['\r', '\n']).strip_prefix("data:") else {
+        return Ok(StreamLine::Ignored);
+    };
+    let data = data.trim();
+    if data == "[DONE]" {
+        return Ok(StreamLine::Done);
+    }
+
+    let chunk: StreamChunk = serde_json::from_str(data).map_err(|_| {
+        let message = error_message(data);
+        if message == data {
+            LlmError::InvalidStream(data.to_string())
+        } else {
+            LlmError::Provider {
+                status: 200,
+                message,
+            }
+        }
+    })?;
+    Ok(chunk
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.delta.content)
+        .filter(|content| !content.is_empty())
+        .map_or(StreamLine::Ignored, StreamLine::Token))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use serde_json::json;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_partial_json, header, method, path},
+    };
+
+    use super::*;
+
+    fn chat(server: &MockServer) -> OpenAiChat {
+        let config =
+            OpenAiChatConfig::new(format!("{}/v1", server.uri()), "test-model").with_api_key("k");
+        OpenAiChat::new(config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn returns_first_choice() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer k"))
+            .and(body_partial_json(json!({
+                "model": "test-model",
+                "messages": [
+                    { "role": "system", "content": "be brief" },
+                    { "role": "user", "content": "hi" }
+                ]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "index": 0, "message": { "role": "assistant", "content": "hello" } }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let request = ChatRequest::new(vec![Message::system("be brief"), Message::user("hi")]);
+        assert_eq!(chat(&server).complete(&request).await.unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn surfaces_provider_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(404)
+                    .set_body_json(json!({ "error": { "message": "no such model" } })),
+            )
+            .mount(&server)
+            .await;
+
+        let err = chat(&server)
+            .complete(&ChatRequest::new(vec![Message::user("hi")]))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, LlmError::Provider { status: 404, ref message } if message == "no such model")
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_sampling_settings() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "temperature": 0.0,
+                "max_tokens": 64,
+                "stop": ["\n\n", "END"]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "ok" } }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let request = ChatRequest::new(vec![Message::user("hi")])
+            .with_temperature(0.0)
+            .with_max_tokens(64)
+            .with_stop("\n\n")
+            .with_stop("END");
+        assert_eq!(chat(&server).complete(&request).await.unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn streams_tokens() {
+        let events = [
+            r#"{"choices":[{"delta":{"role":"assistant","content":""}}]}"#,
+            r#"{"choices":[{"delta":{"content":"Crabs"}}]}"#,
+            r#"{"choices":[{"delta":{"content":" walk"}}]}"#,
+            r#"{"choices":[{"delta":{"content":" sideways."}}]}"#,
+            r#"{"choices":[{"delta":{},"finish_reason":"stop"}]}"#,
+            "[DONE]",
+        ];
+        let body: String = events.iter().map(|e| format!("data: {e}\n\n")).collect();
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "stream": true })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let tokens: Vec<String> = chat(&server)
+            .stream(&ChatRequest::new(vec![Message::user("hi")]))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(tokens, ["Crabs", " walk", " sideways."]);
+    }
+
+    #[test]
+    fn parses_stream_lines() {
+        assert_eq!(parse_line(": keep-alive\n").unwrap(), StreamLine::Ignored);
+        assert_eq!(parse_line("data: [DONE]\r\n").unwrap(), StreamLine::Done);
+        assert_eq!(
+            parse_line(r#"data:{"choices":[{"delta":{"content":"hi"}}]}"#).unwrap(),
+            StreamLine::Token("hi".to_string())
+        );
+        assert!(matches!(
+            parse_line(r#"data: {"error":{"message":"overloaded"}}"#),
+            Err(LlmError::Provider { ref message, .. }) if message == "overloaded"
+        ));
+        assert!(matches!(
+            parse_line("data: not json"),
+            Err(LlmError::InvalidStream(_))
+        ));
+    }
+}
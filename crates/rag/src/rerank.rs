@@ -0,0 +1,31 @@
+mod http;
+mod local;
+
+use async_trait::async_trait;
+
+pub use self::{
+    http::{HttpReranker, HttpRerankerConfig, RerankApi},
+    local::CrossEncoder,
+};
+use crate::error::RerankError;
+
+/// Scores how relevant each of a set of texts is to a query, typically
+/// with a cross-encoder that reads the query and the text together.
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// One relevance score per document, in input order. Higher is more
+    /// relevant; scores are only comparable within one call.
+    async fn score(&self, query: &str, documents: &[&str]) -> Result<Vec<f32>, RerankError>;
+}
+
+/// Checks that a reranker returned one score per document.
+fn check_scores(documents: usize, scores: Vec<f32>) -> Result<Vec<f32>, RerankError> {
+    if scores.len() == documents {
+        Ok(scores)
+    } else {
+        Err(RerankError::CountMismatch {
+            expected: documents,
+            actual: scores.len(),
+        })
+    }
+}
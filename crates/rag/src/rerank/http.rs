@@ -0,0 +1,304 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use embedding::openai::error_message;
+use serde::{Deserialize, Serialize};
+
+use super::{Reranker, check_scores};
+use crate::error::RerankError;
+
+/// Which flavour of `/rerank` request a server expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RerankApi {
+    /// Hugging Face text-embeddings-inference: `{"query", "texts"}`.
+    Tei,
+    /// Cohere and compatible servers (Jina, vLLM, Infinity):
+    /// `{"model", "query", "documents"}`.
+    Cohere,
+}
+
+/// Connection and batching settings for an [`HttpReranker`].
+#[derive(Debug, Clone)]
+pub struct HttpRerankerConfig {
+    /// Root the `/rerank` path is appended to, e.g. `http://localhost:8080`
+    /// for TEI or `https://api.cohere.com/v1` for Cohere.
+    pub base_url: String,
+    pub api: RerankApi,
+    /// Model to ask for; servers that host a single model ignore it.
+    pub model: Option<String>,
+    pub api_key: Option<String>,
+    /// Most documents sent in one request.
+    pub batch_size: usize,
+    pub timeout: Duration,
+}
+
+impl HttpRerankerConfig {
+    pub fn new(base_url: impl Into<String>, api: RerankApi) -> Self {
+        Self {
+            base_url: base_url.into(),
+            api,
+            model: None,
+            api_key: None,
+            batch_size: 32,
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    #[must_use]
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    #[must_use]
+    pub const fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[derive(Serialize)]
+struct RerankRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<&'a str>,
+    query: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    texts: Option<&'a [&'a str]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    documents: Option<&'a [&'a str]>,
+    /// Asks TEI to cut long inputs instead of rejecting them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    truncate: Option<bool>,
+}
+
+/// TEI answers with a bare list, Cohere with the list under `results`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RerankResponse {
+    Tei(Vec<Ranked>),
+    Cohere { results: Vec<Ranked> },
+}
+
+#[derive(Deserialize)]
+struct Ranked {
+    index: usize,
+    #[serde(alias = "relevance_score")]
+    score: f32,
+}
+
+/// Reranker for servers with a `/rerank` endpoint.
+pub struct HttpReranker {
+    client: reqwest::Client,
+    config: HttpRerankerConfig,
+}
+
+impl HttpReranker {
+    pub fn new(config: HttpRerankerConfig) -> Result<Self, RerankError> {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| RerankError::Transport(Box::new(e)))?;
+        Ok(Self::with_client(client, config))
+    }
+
+    #[must_use]
+    pub const fn with_client(client: reqwest::Client, config: HttpRerankerConfig) -> Self {
+        Self { client, config }
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> &HttpRerankerConfig {
+        &self.config
+    }
+
+    /// Scores one batch, which the server may return in any order.
+    async fn request(&self, query: &str, documents: &[&str]) -> Result<Vec<f32>, RerankError> {
+        let url = format!("{}/rerank", self.config.base_url.trim_end_matches('/'));
+        let body = match self.config.api {
+            RerankApi::Tei => RerankRequest {
+                model: None,
+                query,
+                texts: Some(documents),
+                documents: None,
+                truncate: Some(true),
+            },
+            RerankApi::Cohere => RerankRequest {
+                model: self.config.model.as_deref(),
+                query,
+                texts: None,
+                documents: Some(documents),
+                truncate: None,
+            },
+        };
+
+        let mut http = self.client.post(url).json(&body);
+        if let Some(api_key) = &self.config.api_key {
+            http = http.bearer_auth(api_key);
+        }
+        let response = http
+            .send()
+            .await
+            .map_err(|e| RerankError::Transport(Box::new(e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(RerankError::Provider {
+                status: status.as_u16(),
+                message: error_message(&response.text().await.unwrap_or_default()),
+            });
+        }
+
+        let body: RerankResponse = response.json().await.map_err(|e| RerankError::Provider {
+            status: status.as_u16(),
+            message: format!("invalid response body: {e}"),
+        })?;
+        let (RerankResponse::Tei(ranked) | RerankResponse::Cohere { results: ranked }) = body;
+
+        let mut scores = vec![None; documents.len()];
+        for Ranked { index, score } in ranked {
+            if let Some(slot) = scores.get_mut(index) {
+                *slot = Some(score);
+            }
+        }
+        let scores: Vec<f32> = scores.into_iter().flatten().collect();
+        check_scores(documents.len(), scores)
+    }
+}
+
+#[async_trait]
+impl Reranker for HttpReranker {
+    async fn score(&self, query: &str, documents: &[&str]) -> Result<Vec<f32>, RerankError> {
+        let mut scores = Vec::with_capacity(documents.len());
+        for batch in documents.chunks(self.config.batch_size.max(1)) {
+            scores.extend(self.request(query, batch).await?);
+        }
+        Ok(scores)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_json, header, method, path},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn scores_with_tei_in_input_order() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/rerank"))
+            .and(body_json(json!({
+                "query": "crabs",
+                "texts": ["revenue", "crab walks", "shells"],
+                "truncate": true
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                { "index": 1, "score": 0.9 },
+                { "index": 2, "score": 0.4 },
+                { "index": 0, "score": 0.1 }
+            ])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let reranker =
+            HttpReranker::new(HttpRerankerConfig::new(server.uri(), RerankApi::Tei)).unwrap();
+        let scores = reranker
+            .score("crabs", &["revenue", "crab walks", "shells"])
+            .await
+            .unwrap();
+        assert_eq!(scores, [0.1, 0.9, 0.4]);
+    }
+
+    #[tokio::test]
+    async fn scores_with_cohere_in_batches() {
+        let server = MockServer::start().await;
+        for (documents, results) in [
+            (
+                json!(["a", "b"]),
+                json!([{ "index": 1, "relevance_score": 0.8 }, { "index": 0, "relevance_score": 0.2 }]),
+            ),
+            (
+                json!(["c"]),
+                json!([{ "index": 0, "relevance_score": 0.5 }]),
+            ),
+        ] {
+            Mock::given(method("POST"))
+                .and(path("/v1/rerank"))
+                .and(header("authorization", "Bearer k"))
+                .and(body_json(json!({
+                    "model": "rerank-v3.5",
+                    "query": "q",
+                    "documents": documents
+                })))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(json!({ "id": "x", "results": results })),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let config = HttpRerankerConfig::new(format!("{}/v1", server.uri()), RerankApi::Cohere)
+            .with_model("rerank-v3.5")
+            .with_api_key("k")
+            .with_batch_size(2);
+        let scores = HttpReranker::new(config)
+            .unwrap()
+            .score("q", &["a", "b", "c"])
+            .await
+            .unwrap();
+        assert_eq!(scores, [0.2, 0.8, 0.5]);
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_scores_and_surfaces_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_json(
+                json!({ "query": "q", "texts": ["a", "b"], "truncate": true }),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!([{ "index": 0, "score": 1.0 }])),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(413).set_body_json(json!({ "error": "batch too large" })),
+            )
+            .mount(&server)
+            .await;
+
+        let reranker =
+            HttpReranker::new(HttpRerankerConfig::new(server.uri(), RerankApi::Tei)).unwrap();
+        assert!(matches!(
+            reranker.score("q", &["a", "b"]).await,
+            Err(RerankError::CountMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+        assert!(matches!(
+            reranker.score("q", &["a", "b", "c"]).await,
+            Err(RerankError::Provider { status: 413, ref message }) if message == "batch too large"
+        ));
+    }
+}
@@ -0,0 +1,337 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use async_trait::async_trait;
+use candle_core::{Device, Tensor};
+use candle_nn::{Linear, Module, VarBuilder};
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+use serde::Deserialize;
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+
+use super::{Reranker, check_scores};
+use crate::error::RerankError;
+
+/// Sequence length used when the model config sets none smaller.
+const DEFAULT_MAX_SEQ_LENGTH: usize = 512;
+
+/// The classification head settings of a `BertForSequenceClassification`
+/// config.
+#[derive(Deserialize)]
+struct ClassifierConfig {
+    num_labels: Option<usize>,
+    id2label: Option<HashMap<String, String>>,
+}
+
+struct LocalModel {
+    bert: BertModel,
+    pooler: Linear,
+    classifier: Linear,
+    num_labels: usize,
+    tokenizer: Tokenizer,
+    device: Device,
+}
+
+/// Runs a BERT cross-encoder (such as `cross-encoder/ms-marco-MiniLM-L-6-v2`)
+/// on the CPU. The query and each document are read together as one
+/// sequence pair. With a single-label head the score is the sigmoid of its
+/// logit, as sentence-transformers' `CrossEncoder` reports it; with a
+/// two-label head it is the softmax probability of label 1, "relevant".
+pub struct CrossEncoder {
+    inner: Arc<LocalModel>,
+    batch_size: usize,
+}
+
+impl CrossEncoder {
+    /// Loads a model directory holding `config.json`, `tokenizer.json` and
+    /// `model.safetensors`.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, RerankError> {
+        let dir = dir.as_ref();
+        let config: Config = read_json(&dir.join("config.json"))?;
+        let head: ClassifierConfig = read_json(&dir.join("config.json"))?;
+        let num_labels = head
+            .num_labels
+            .or_else(|| head.id2label.map(|labels| labels.len()))
+            .unwrap_or(1)
+            .max(1);
+        if num_labels > 2 {
+            return Err(RerankError::Model(
+                format!("expected a one- or two-label classifier, found {num_labels} labels")
+                    .into(),
+            ));
+        }
+
+        let mut tokenizer =
+            Tokenizer::from_file(dir.join("tokenizer.json")).map_err(RerankError::Model)?;
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: DEFAULT_MAX_SEQ_LENGTH.min(config.max_position_embeddings),
+                ..TruncationParams::default()
+            }))
+            .map_err(RerankError::Model)?;
+        tokenizer.with_padding(Some(PaddingParams {
+            pad_id: u32::try_from(config.pad_token_id).unwrap_or_default(),
+            ..PaddingParams::default()
+        }));
+
+        let device = Device::Cpu;
+        let weights = [dir.join("model.safetensors")];
+        // SAFETY: the weights file is memory-mapped read-only and must not
+        // be modified while the model is loaded.
+        let vb = unsafe { VarBuilder::from_mmaped_safetensors(&weights, DTYPE, &device) }
+            .map_err(model_error)?;
+
+        // Hugging Face checkpoints nest the encoder and pooler under
+        // `bert.`; the classifier is always at the root.
+        let prefix = if vb.contains_tensor("bert.pooler.dense.weight") {
+            "bert."
+        } else {
+            ""
+        };
+        let bert = BertModel::load(vb.clone(), &config).map_err(model_error)?;
+        let pooler = candle_nn::linear(
+            config.hidden_size,
+            config.hidden_size,
+            vb.pp(format!("{prefix}pooler.dense")),
+        )
+        .map_err(model_error)?;
+        let classifier = candle_nn::linear(config.hidden_size, num_labels, vb.pp("classifier"))
+            .map_err(model_error)?;
+
+        Ok(Self {
+            inner: Arc::new(LocalModel {
+                bert,
+                pooler,
+                classifier,
+                num_labels,
+                tokenizer,
+                device,
+            }),
+            batch_size: 16,
+        })
+    }
+
+    #[must_use]
+    pub const fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+}
+
+impl LocalModel {
+    fn score(&self, query: &str, documents: &[String]) -> Result<Vec<f32>, RerankError> {
+        let pairs: Vec<(String, String)> = documents
+            .iter()
+            .map(|document| (query.to_string(), document.clone()))
+            .collect();
+        let encodings = self
+            .tokenizer
+            .encode_batch(pairs, true)
+            .map_err(RerankError::Model)?;
+
+        let tensors = |field: fn(&tokenizers::Encoding) -> &[u32]| {
+            encodings
+                .iter()
+                .map(|e| Tensor::new(field(e), &self.device))
+                .collect::<candle_core::Result<Vec<_>>>()
+                .and_then(|rows| Tensor::stack(&rows, 0))
+                .map_err(model_error)
+        };
+        let ids = tensors(tokenizers::Encoding::get_ids)?;
+        let type_ids = tensors(tokenizers::Encoding::get_type_ids)?;
+        let mask = tensors(tokenizers::Encoding::get_attention_mask)?;
+
+        self.forward(&ids, &type_ids, &mask).map_err(model_error)
+    }
+
+    fn forward(
+        &self,
+        ids: &Tensor,
+        type_ids: &Tensor,
+        mask: &Tensor,
+    ) -> candle_core::Result<Vec<f32>> {
+        let hidden = self.bert.forward(ids, type_ids, Some(mask))?;
+        // BERT's pooled output: the [CLS] token through a dense layer and tanh.
+        let cls = hidden.narrow(1, 0, 1)?.squeeze(1)?;
+        let pooled = self.pooler.forward(&cls)?.tanh()?;
+        let logits = self.classifier.forward(&pooled)?;
+        if self.num_labels == 2 {
+            candle_nn::ops::softmax(&logits, 1)?
+                .narrow(1, 1, 1)?
+                .squeeze(1)?
+                .to_vec1()
+        } else {
+            candle_nn::ops::sigmoid(&logits.squeeze(1)?)?.to_vec1()
+        }
+    }
+}
+
+#[async_trait]
+impl Reranker for CrossEncoder {
+    async fn score(&self, query: &str, documents: &[&str]) -> Result<Vec<f32>, RerankError> {
+        let mut scores = Vec::with_capacity(documents.len());
+        for batch in documents.chunks(self.batch_size.max(1)) {
+            let inner = Arc::clone(&self.inner);
+            let query = query.to_string();
+            let owned: Vec<String> = batch.iter().map(|d| (*d).to_string()).collect();
+            let batch_scores = tokio::task::spawn_blocking(move || inner.score(&query, &owned))
+                .await
+                .map_err(|e| RerankError::Model(Box::new(e)))??;
+            scores.extend(batch_scores);
+        }
+        check_scores(documents.len(), scores)
+    }
+}
+
+fn model_error(e: candle_core::Error) -> RerankError {
+    RerankError::Model(Box::new(e))
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T, RerankError> {
+    let data = std::fs::read_to_string(path).map_err(|e| RerankError::Model(Box::new(e)))?;
+    serde_json::from_str(&data).map_err(|e| RerankError::Model(Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use candle_nn::VarMap;
+    use serde_json::json;
+
+    use super::*;
+
+    const VOCAB: &[&str] = &[
+        "[PAD]", "[UNK]", "[CLS]", "[SEP]", "the", "crab", "walks", "sideways", "on", "sand", ".",
+    ];
+
+    /// Writes a tiny randomly initialised BERT cross-encoder with
+    /// `num_labels` outputs in the Hugging Face layout.
+    fn write_tiny_model(dir: &Path, num_labels: usize) {
+        let id2label: serde_json::Map<_, _> = (0..num_labels)
+            .map(|i| (i.to_string(), json!(format!("LABEL_{i}"))))
+            .collect();
+        let config = json!({
+            "vocab_size": VOCAB.len(),
+            "hidden_size": 8,
+            "num_hidden_layers": 1,
+            "num_attention_heads": 2,
+            "intermediate_size": 16,
+            "hidden_act": "gelu",
+            "hidden_dropout_prob": 0.0,
+            "max_position_embeddings": 32,
+            "type_vocab_size": 2,
+            "initializer_range": 0.02,
+            "layer_norm_eps": 1e-12,
+            "pad_token_id": 0,
+            "model_type": "bert",
+            "id2label": id2label
+        });
+        std::fs::write(dir.join("config.json"), config.to_string()).unwrap();
+
+        let vocab: serde_json::Map<_, _> = VOCAB
+            .iter()
+            .enumerate()
+            .map(|(i, token)| ((*token).to_string(), json!(i)))
+            .collect();
+        let tokenizer = json!({
+            "version": "1.0",
+            "truncation": null,
+            "padding": null,
+            "added_tokens": [],
+            "normalizer": { "type": "BertNormalizer", "clean_text": true,
+                "handle_chinese_chars": true, "strip_accents": null, "lowercase": true },
+            "pre_tokenizer": { "type": "BertPreTokenizer" },
+            "post_processor": { "type": "BertProcessing", "sep": ["[SEP]", 3], "cls": ["[CLS]", 2] },
+            "decoder": null,
+            "model": { "type": "WordPiece", "unk_token": "[UNK]",
+                "continuing_subword_prefix": "##", "max_input_chars_per_word": 100, "vocab": vocab }
+        });
+        std::fs::write(dir.join("tokenizer.json"), tokenizer.to_string()).unwrap();
+
+        let config: Config = serde_json::from_value(config).unwrap();
+        let varmap = VarMap::new();
+        let vb = VarBuilder::from_varmap(&varmap, DTYPE, &Device::Cpu);
+        BertModel::load(vb.pp("bert"), &config).unwrap();
+        candle_nn::linear(8, 8, vb.pp("bert.pooler.dense")).unwrap();
+        candle_nn::linear(8, num_labels, vb.pp("classifier")).unwrap();
+        varmap.save(dir.join("model.safetensors")).unwrap();
+    }
+
+    fn tiny_model_dir(name: &str, num_labels: usize) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "crabtrap-cross-encoder-{name}-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_tiny_model(&dir, num_labels);
+        dir
+    }
+
+    #[tokio::test]
+    async fn scores_each_document_independently_of_batching() {
+        let dir = tiny_model_dir("batching", 1);
+        let documents = [
+            "the crab walks sideways",
+            "sand",
+            "the crab walks on the sand .",
+        ];
+
+        let batched = CrossEncoder::load(&dir).unwrap();
+        let one_by_one = CrossEncoder::load(&dir).unwrap().with_batch_size(1);
+        let scores = batched.score("crab", &documents).await.unwrap();
+        let single = one_by_one.score("crab", &documents).await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(scores.len(), 3);
+        assert!(scores.iter().all(|s| (0.0..=1.0).contains(s)));
+        for (a, b) in scores.iter().zip(&single) {
+            assert!((a - b).abs() < 1e-4, "{a} != {b}");
+        }
+    }
+
+    #[tokio::test]
+    async fn two_label_heads_score_the_relevant_label() {
+        let two_labels = tiny_model_dir("two-labels", 2);
+        let one_label = tiny_model_dir("one-label", 1);
+
+        // softmax(l)[1] = sigmoid(l1 - l0), so a one-label head holding the
+        // difference of the two rows must score the same.
+        let weights = two_labels.join("model.safetensors");
+        let mut tensors = candle_core::safetensors::load(&weights, &Device::Cpu).unwrap();
+        for name in ["classifier.weight", "classifier.bias"] {
+            let rows = &tensors[name];
+            let difference = (rows.get(1).unwrap() - rows.get(0).unwrap())
+                .unwrap()
+                .unsqueeze(0)
+                .unwrap();
+            tensors.insert(name.to_string(), difference);
+        }
+        candle_core::safetensors::save(&tensors, one_label.join("model.safetensors")).unwrap();
+
+        let documents = ["the crab walks sideways", "sand", "on the sand ."];
+        let scores = CrossEncoder::load(&two_labels)
+            .unwrap()
+            .score("crab", &documents)
+            .await
+            .unwrap();
+        let expected = CrossEncoder::load(&one_label)
+            .unwrap()
+            .score("crab", &documents)
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&two_labels).unwrap();
+        std::fs::remove_dir_all(&one_label).unwrap();
+
+        for (a, b) in scores.iter().zip(&expected) {
+            assert!((a - b).abs() < 1e-4, "{a} != {b}");
+        }
+    }
+
+    #[test]
+    fn rejects_heads_with_more_than_two_labels() {
+        let dir = tiny_model_dir("three-labels", 3);
+        let loaded = CrossEncoder::load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(loaded, Err(RerankError::Model(_))));
+    }
+}
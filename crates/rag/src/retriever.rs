@@ -0,0 +1,657 @@
+use std::{collections::HashMap, sync::Arc};
+
+use domain::{
+    chunk::Chunk,
+    ids::{ChunkId, ObservationId},
+    search::{ObservationSummary, SearchHit, SearchQuery},
+};
+use embedding::{EmbedError, Embedder};
+
+use crate::{error::Result, index::ChunkIndex, rerank::Reranker};
+
+/// How a [`HybridRetriever`] gathers and fuses candidates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetrieverConfig {
+    /// Candidates fetched from each signal before fusion.
+    pub candidates: usize,
+    /// Reciprocal rank fusion constant. Larger values shrink the lead of
+    /// the top ranks over the ones below them.
+    pub rrf_k: f32,
+    /// Weight of the full-text ranking. Zero skips keyword search.
+    pub keyword_weight: f32,
+    /// Weight of the embedding ranking. Zero skips vector search.
+    pub vector_weight: f32,
+    /// Share of the shorter of two chunks of one observation that may
+    /// overlap the other before the lower-ranked one is dropped.
+    pub max_overlap: f32,
+    /// Fused results handed to the reranker, if there is one. The best of
+    /// them by rerank score are kept.
+    pub rerank_candidates: usize,
+    /// Maximal marginal relevance trade-off between a result's relevance
+    /// and its similarity to results already chosen. One ranks by relevance
+    /// alone; lower values favour results unlike the ones before them,
+    /// judged by their embeddings.
+    pub mmr_lambda: f32,
+    /// Most results taken from one observation, or no limit.
+    pub max_per_observation: Option<usize>,
+}
+
+impl Default for RetrieverConfig {
+    fn default() -> Self {
+        Self {
+            candidates: 50,
+            rrf_k: 60.0,
+            keyword_weight: 1.0,
+            vector_weight: 1.0,
+            max_overlap: 0.5,
+            rerank_candidates: 50,
+            mmr_lambda: 1.0,
+            max_per_observation: None,
+        }
+    }
+}
+
+impl RetrieverConfig {
+    #[must_use]
+    pub const fn with_candidates(mut self, candidates: usize) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_rrf_k(mut self, rrf_k: f32) -> Self {
+        self.rrf_k = rrf_k;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_weights(mut self, keyword: f32, vector: f32) -> Self {
+        self.keyword_weight = keyword;
+        self.vector_weight = vector;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_max_overlap(mut self, max_overlap: f32) -> Self {
+        self.max_overlap = max_overlap;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_rerank_candidates(mut self, rerank_candidates: usize) -> Self {
+        self.rerank_candidates = rerank_candidates;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_mmr_lambda(mut self, mmr_lambda: f32) -> Self {
+        self.mmr_lambda = mmr_lambda;
+        self
+    }
+
+    /// Keeps at most `max` results (at least one) from any observation.
+    #[must_use]
+    pub fn with_max_per_observation(mut self, max: usize) -> Self {
+        self.max_per_observation = Some(max.max(1));
+        self
+    }
+}
+
+/// Where a chunk placed in one signal's results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SignalScore {
+    /// Position in the signal's results, starting at 1.
+    pub rank: usize,
+    /// The signal's own score: text rank or cosine similarity.
+    pub score: f32,
+}
+
+/// A chunk chosen by hybrid retrieval, with the scores that placed it.
+#[derive(Debug, Clone)]
+pub struct RetrievedChunk {
+    pub chunk: Chunk,
+    pub observation: ObservationSummary,
+    /// Keyword search excerpt, if the chunk matched the query's words.
+    pub snippet: Option<String>,
+    /// The weighted reciprocal rank fusion score.
+    pub score: f32,
+    pub keyword: Option<SignalScore>,
+    pub vector: Option<SignalScore>,
+    /// The reranker's relevance score. Relevance is judged by it when a
+    /// reranker is used, and by the fusion score otherwise.
+    pub rerank_score: Option<f32>,
+}
+
+/// Runs keyword and vector search side by side and fuses their rankings
+/// with weighted reciprocal rank fusion, optionally reordering the best
+/// fused results with a reranker, then picks a varied set of them by
+/// maximal marginal relevance.
+pub struct HybridRetriever {
+    index: Arc<dyn ChunkIndex>,
+    embedder: Arc<dyn Embedder>,
+    reranker: Option<Arc<dyn Reranker>>,
+    config: RetrieverConfig,
+}
+
+impl HybridRetriever {
+    pub fn new(index: Arc<dyn ChunkIndex>, embedder: Arc<dyn Embedder>) -> Self {
+        Self {
+            index,
+            embedder,
+            reranker: None,
+            config: RetrieverConfig::default(),
+        }
+    }
+
+    /// Reranks the best [`RetrieverConfig::rerank_candidates`] fused
+    /// results and keeps the best of those.
+    #[must_use]
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.reranker = Some(reranker);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_config(mut self, config: RetrieverConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> &RetrieverConfig {
+        &self.config
+    }
+
+    /// The best chunks for `query`, at most one of any set of duplicate or
+    /// overlapping chunks of the same observation and no more than
+    /// [`RetrieverConfig::max_per_observation`] of any observation.
+    pub async fn retrieve(&self, query: &SearchQuery) -> Result<Vec<RetrievedChunk>> {
+        let SearchQuery {
+            text,
+            limit,
+            filters,
+        } = query;
+        let candidates = self.config.candidates.max(*limit);
+
+        let keyword = async {
+            if self.config.keyword_weight > 0.0 {
+                self.index.keyword_search(text, candidates, filters).await
+            } else {
+                Ok(Vec::new())
+            }
+        };
+        let vector =
+            async {
+                if self.config.vector_weight > 0.0 {
+                    let vector = self.embedder.embed(&[text]).await?.pop().ok_or(
+                        EmbedError::CountMismatch {
+                            expected: 1,
+                            actual: 0,
+                        },
+                    )?;
+                    self.index
+                        .vector_search(self.embedder.model_id(), &vector, candidates, filters)
+                        .await
+                } else {
+                    Ok(Vec::new())
+                }
+            };
+        let (keyword, vector) = tokio::try_join!(keyword, vector)?;
+
+        let mut fused = dedupe(fuse(keyword, vector, &self.config), self.config.max_overlap);
+        if let Some(reranker) = &self.reranker {
+            fused.truncate(self.config.rerank_candidates.max(*limit));
+            fused = rerank(reranker.as_ref(), text, fused).await?;
+        }
+
+        let embeddings = if self.config.mmr_lambda < 1.0 && !fused.is_empty() {
+            let ids: Vec<ChunkId> = fused.iter().map(|r| r.chunk.id()).collect();
+            self.index
+                .embeddings(self.embedder.model_id(), &ids)
+                .await?
+                .into_iter()
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        Ok(select(fused, *limit, &self.config, &embeddings))
+    }
+}
+
+/// Picks up to `limit` of the ranked `candidates` one at a time, each
+/// maximizing `λ · relevance - (1 - λ) · similarity` to the closest result
+/// already picked, and skipping observations that have reached their cap.
+///
+/// Relevance is the rerank or fusion score scaled to 0..=1 over the
+/// candidates; similarity is the cosine of the chunks' embeddings, taken as
+/// zero when either has none. With λ = 1 this keeps the ranking's order.
+fn select(
+    candidates: Vec<RetrievedChunk>,
+    limit: usize,
+    config: &RetrieverConfig,
+    embeddings: &HashMap<ChunkId, Vec<f32>>,
+) -> Vec<RetrievedChunk> {
+    let lambda = config.mmr_lambda.clamp(0.0, 1.0);
+    let relevance = scaled_relevance(&candidates);
+    let similarity = |a: &RetrievedChunk, b: &RetrievedChunk| match (
+        embeddings.get(&a.chunk.id()),
+        embeddings.get(&b.chunk.id()),
+    ) {
+        (Some(a), Some(b)) => cosine(a, b),
+        _ => 0.0,
+    };
+
+    let mut open: Vec<usize> = (0..candidates.len()).collect();
+    let mut picked: Vec<usize> = Vec::with_capacity(limit.min(candidates.len()));
+    let mut per_observation: HashMap<ObservationId, usize> = HashMap::new();
+
+    while picked.len() < limit {
+        let mut best: Option<(usize, f32)> = None;
+        for (position, &i) in open.iter().enumerate() {
+            let observation = candidates[i].chunk.observation_id();
+            if let Some(max) = config.max_per_observation
+                && per_observation.get(&observation).copied().unwrap_or(0) >= max
+            {
+                continue;
+            }
+
+            let redundancy = picked
+                .iter()
+                .map(|&j| similarity(&candidates[i], &candidates[j]))
+                .fold(0.0, f32::max);
+            let score = lambda * relevance[i] - (1.0 - lambda) * redundancy;
+            if best.is_none_or(|(_, best)| score > best) {
+                best = Some((position, score));
+            }
+        }
+
+        let Some((position, _)) = best else {
+            break;
+        };
+        let i = open.remove(position);
+        *per_observation
+            .entry(candidates[i].chunk.observation_id())
+            .or_default() += 1;
+        picked.push(i);
+    }
+
+    let mut candidates: Vec<Option<RetrievedChunk>> = candidates.into_iter().map(Some).collect();
+    picked
+        .into_iter()
+        .filter_map(|i| candidates[i].take())
+        .collect()
+}
+
+/// Each candidate's rerank score, or fusion score without one, min-max
+/// scaled so the best is 1 and the worst 0.
+fn scaled_relevance(candidates: &[RetrievedChunk]) -> Vec<f32> {
+    let raw: Vec<f32> = candidates
+        .iter()
+        .map(|r| r.rerank_score.unwrap_or(r.score))
+        .collect();
+    let min = raw.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = raw.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let range = max - min;
+    raw.into_iter()
+        .map(|x| if range > 0.0 { (x - min) / range } else { 1.0 })
+        .collect()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms > 0.0 { dot / norms } else { 0.0 }
+}
+
+/// Orders `results` by the reranker's scores, best first.
+async fn rerank(
+    reranker: &dyn Reranker,
+    query: &str,
+    mut results: Vec<RetrievedChunk>,
+) -> Result<Vec<RetrievedChunk>> {
+    let documents: Vec<&str> = results.iter().map(|r| r.chunk.text()).collect();
+    let scores = reranker.score(query, &documents).await?;
+    for (result, score) in results.iter_mut().zip(scores) {
+        result.rerank_score = Some(score);
+    }
+    results.sort_by(|a, b| {
+        b.rerank_score
+            .unwrap_or(f32::MIN)
+            .total_cmp(&a.rerank_score.unwrap_or(f32::MIN))
+    });
+    Ok(results)
+}
+
+/// Merges both rankings, scoring each chunk `Σ weight / (rrf_k + rank)`
+/// over the signals that found it. Ties keep keyword order first.
+fn fuse(
+    keyword: Vec<SearchHit>,
+    vector: Vec<SearchHit>,
+    config: &RetrieverConfig,
+) -> Vec<RetrievedChunk> {
+    let mut fused: Vec<RetrievedChunk> = Vec::with_capacity(keyword.len() + vector.len());
+    let mut positions = HashMap::new();
+
+    let signals = [
+        (keyword, config.keyword_weight, true),
+        (vector, config.vector_weight, false),
+    ];
+    for (hits, weight, is_keyword) in signals {
+        for (i, hit) in hits.into_iter().enumerate() {
+            let signal = SignalScore {
+                rank: i + 1,
+                score: hit.score,
+            };
+            let contribution = weight / (config.rrf_k + signal.rank as f32);
+
+            let position = *positions.entry(hit.chunk.id()).or_insert_with(|| {
+                fused.push(RetrievedChunk {
+                    chunk: hit.chunk,
+                    observation: hit.observation,
+                    snippet: None,
+                    score: 0.0,
+                    keyword: None,
+                    vector: None,
+                    rerank_score: None,
+                });
+                fused.len() - 1
+            });
+
+            let entry = &mut fused[position];
+            entry.score += contribution;
+            entry.snippet = entry.snippet.take().or(hit.snippet);
+            if is_keyword {
+                entry.keyword = Some(signal);
+            } else {
+                entry.vector = Some(signal);
+            }
+        }
+    }
+
+    fused.sort_by(|a, b| b.score.total_cmp(&a.score));
+    fused
+}
+
+/// Drops every chunk that repeats the text of, or overlaps by more than
+/// `max_overlap`, a better-ranked chunk of the same observation.
+fn dedupe(fused: Vec<RetrievedChunk>, max_overlap: f32) -> Vec<RetrievedChunk> {
+    let mut kept: Vec<RetrievedChunk> = Vec::with_capacity(fused.len());
+
+    for candidate in fused {
+        let redundant = kept.iter().any(|better| {
+            better.chunk.observation_id() == candidate.chunk.observation_id()
+                && (better.chunk.text() == candidate.chunk.text()
+                    || overlap_ratio(&better.chunk, &candidate.chunk) > max_overlap)
+        });
+        if !redundant {
+            kept.push(candidate);
+        }
+    }
+
+    kept
+}
+
+/// How much of the shorter chunk's byte range the two chunks share.
+fn overlap_ratio(a: &Chunk, b: &Chunk) -> f32 {
+    let start = a.start_offset().max(b.start_offset());
+    let end = a.end_offset().min(b.end_offset());
+    let shorter = (a.end_offset() - a.start_offset()).min(b.end_offset() - b.start_offset());
+    if end <= start || shorter == 0 {
+        return 0.0;
+    }
+    (end - start) as f32 / shorter as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use domain::ids::ObservationId;
+    use embedding::HashingEmbedder;
+
+    use super::*;
+    use crate::testing::{FakeIndex, LengthEmbedder, LengthReranker, MemoryIndex, hit};
+
+    #[test]
+    fn fuses_rankings_and_keeps_signal_scores() {
+        let (a, b) = (ObservationId::new(), ObservationId::new());
+        let both = hit(a, "found by both", 0, 0.3);
+        let keyword_only = hit(b, "keyword only", 0, 0.9);
+        let vector_only = hit(b, "vector only", 100, 0.8);
+
+        let mut both_vector = both.clone();
+        both_vector.score = 0.7;
+
+        let fused = fuse(
+            vec![keyword_only.clone(), both.clone()],
+            vec![vector_only.clone(), both_vector],
+            &RetrieverConfig::default(),
+        );
+
+        assert_eq!(fused.len(), 3);
+        assert_eq!(fused[0].chunk.id(), both.chunk.id());
+        assert_eq!(
+            fused[0].keyword,
+            Some(SignalScore {
+                rank: 2,
+                score: 0.3
+            })
+        );
+        assert_eq!(
+            fused[0].vector,
+            Some(SignalScore {
+                rank: 2,
+                score: 0.7
+            })
+        );
+        assert!((fused[0].score - 2.0 / 62.0).abs() < 1e-6);
+        assert_eq!(fused[1].chunk.id(), keyword_only.chunk.id());
+        assert!(fused[1].vector.is_none());
+        assert_eq!(fused[2].chunk.id(), vector_only.chunk.id());
+    }
+
+    #[test]
+    fn weights_favour_a_signal() {
+        let id = ObservationId::new();
+        let keyword_first = hit(id, "keyword", 0, 1.0);
+        let vector_first = hit(id, "vector", 100, 1.0);
+
+        let config = RetrieverConfig::default().with_weights(0.5, 2.0);
+        let fused = fuse(vec![keyword_first], vec![vector_first.clone()], &config);
+
+        assert_eq!(fused[0].chunk.id(), vector_first.chunk.id());
+    }
+
+    #[test]
+    fn drops_duplicate_and_overlapping_chunks_of_one_observation() {
+        let (a, b) = (ObservationId::new(), ObservationId::new());
+        let best = hit(a, "0123456789", 0, 1.0);
+        let overlapping = hit(a, "456789abcd", 4, 1.0);
+        let touching = hit(a, "89abcdefghijklmnopqr", 8, 1.0);
+        let duplicate = hit(a, "0123456789", 500, 1.0);
+        let other_observation = hit(b, "0123456789", 0, 1.0);
+
+        let fused = fuse(
+            vec![
+                best.clone(),
+                overlapping,
+                touching.clone(),
+                duplicate,
+                other_observation.clone(),
+            ],
+            Vec::new(),
+            &RetrieverConfig::default(),
+        );
+        let kept: Vec<_> = dedupe(fused, 0.5)
+            .into_iter()
+            .map(|r| r.chunk.id())
+            .collect();
+
+        assert_eq!(
+            kept,
+            [
+                best.chunk.id(),
+                touching.chunk.id(),
+                other_observation.chunk.id()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn retrieves_from_both_signals() {
+        let id = ObservationId::new();
+        let mut keyword = hit(id, "crabs walk sideways", 0, 0.5);
+        keyword.snippet = Some("**crabs** walk".to_string());
+        let vector = hit(id, "shore dwelling crustaceans", 200, 0.9);
+
+        let index = Arc::new(FakeIndex::new(
+            vec![keyword.clone()],
+            vec![vector.clone(), keyword.clone()],
+        ));
+        let retriever = HybridRetriever::new(index.clone(), Arc::new(LengthEmbedder));
+
+        let results = retriever
+            .retrieve(&SearchQuery::new("crabs", 5))
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].chunk.id(), keyword.chunk.id());
+        assert_eq!(results[0].snippet.as_deref(), Some("**crabs** walk"));
+        assert_eq!(results[1].chunk.id(), vector.chunk.id());
+        assert_eq!(
+            *index.vector_queries.lock().unwrap(),
+            [("length".to_string(), vec![5.0])]
+        );
+    }
+
+    #[tokio::test]
+    async fn zero_weight_skips_a_signal() {
+        let id = ObservationId::new();
+        let index = Arc::new(FakeIndex::new(
+            vec![hit(id, "keyword", 0, 1.0)],
+            vec![hit(id, "vector", 100, 1.0)],
+        ));
+        let retriever = HybridRetriever::new(index.clone(), Arc::new(LengthEmbedder))
+            .with_config(RetrieverConfig::default().with_weights(1.0, 0.0));
+
+        let results = retriever
+            .retrieve(&SearchQuery::new("crabs", 5))
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert!(results[0].vector.is_none());
+        assert!(index.vector_queries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn ranks_similar_text_first_with_a_hashing_embedder() {
+        let embedder = Arc::new(HashingEmbedder::default());
+        let texts = [
+            "Quarterly revenue grew by four percent",
+            "The hermit crab moved into a larger shell",
+            "Crabs walk sideways along the shore",
+        ];
+        let index = Arc::new(MemoryIndex::embed(embedder.as_ref(), &texts).await);
+        let retriever = HybridRetriever::new(index, embedder);
+
+        let results = retriever
+            .retrieve(&SearchQuery::new("crab walking sideways on the shore", 2))
+            .await
+            .unwrap();
+
+        let ranked: Vec<&str> = results.iter().map(|r| r.chunk.text()).collect();
+        assert_eq!(ranked, [texts[2], texts[1]]);
+    }
+
+    #[tokio::test]
+    async fn reranks_the_best_candidates_and_keeps_k() {
+        let id = ObservationId::new();
+        let hits = vec![
+            hit(id, "short", 0, 1.0),
+            hit(id, "a bit longer", 100, 1.0),
+            hit(id, "the longest text of all", 200, 1.0),
+        ];
+        let retriever = HybridRetriever::new(
+            Arc::new(FakeIndex::new(hits.clone(), Vec::new())),
+            Arc::new(LengthEmbedder),
+        )
+        .with_reranker(Arc::new(LengthReranker))
+        .with_config(
+            RetrieverConfig::default()
+                .with_weights(1.0, 0.0)
+                .with_rerank_candidates(2),
+        );
+
+        let results = retriever.retrieve(&SearchQuery::new("q", 1)).await.unwrap();
+
+        // Only the two best fused results are reranked, so the longest text,
+        // fused third, cannot win.
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].chunk.id(), hits[1].chunk.id());
+        assert_eq!(results[0].rerank_score, Some(12.0));
+        assert_eq!(results[0].keyword.map(|k| k.rank), Some(2));
+    }
+
+    #[tokio::test]
+    async fn mmr_passes_over_near_duplicates() {
+        let embedder = Arc::new(HashingEmbedder::default());
+        let texts = [
+            "Crabs walk sideways along the shore, scientists say",
+            "Crabs walk sideways along the shore, scientists said",
+            "Crabs walk sideways along the shore, say scientists",
+            "Hermit crabs swap shells on the shore",
+        ];
+        let index = Arc::new(MemoryIndex::embed(embedder.as_ref(), &texts).await);
+        let query = SearchQuery::new("crabs walk sideways on the shore", 2);
+
+        let relevance_only = HybridRetriever::new(index.clone(), embedder.clone())
+            .retrieve(&query)
+            .await
+            .unwrap();
+        let diverse = HybridRetriever::new(index, embedder)
+            .with_config(RetrieverConfig::default().with_mmr_lambda(0.5))
+            .retrieve(&query)
+            .await
+            .unwrap();
+
+        let ranked = |results: &[RetrievedChunk]| -> Vec<String> {
+            results.iter().map(|r| r.chunk.text().to_string()).collect()
+        };
+        assert!(
+            ranked(&relevance_only)
+                .iter()
+                .all(|t| t.starts_with("Crabs walk"))
+        );
+        assert_eq!(ranked(&diverse)[0], ranked(&relevance_only)[0]);
+        assert_eq!(ranked(&diverse)[1], texts[3]);
+    }
+
+    #[tokio::test]
+    async fn caps_results_per_observation() {
+        let (a, b) = (ObservationId::new(), ObservationId::new());
+        let hits = vec![
+            hit(a, "first part", 0, 1.0),
+            hit(a, "second part", 100, 1.0),
+            hit(a, "third part", 200, 1.0),
+            hit(b, "another observation", 0, 1.0),
+        ];
+        let retriever = HybridRetriever::new(
+            Arc::new(FakeIndex::new(hits.clone(), Vec::new())),
+            Arc::new(LengthEmbedder),
+        )
+        .with_config(
+            RetrieverConfig::default()
+                .with_weights(1.0, 0.0)
+                .with_max_per_observation(2),
+        );
+
+        let results = retriever.retrieve(&SearchQuery::new("q", 3)).await.unwrap();
+
+        let ids: Vec<_> = results.iter().map(|r| r.chunk.id()).collect();
+        assert_eq!(
+            ids,
+            [hits[0].chunk.id(), hits[1].chunk.id(), hits[3].chunk.id()]
+        );
+    }
+}
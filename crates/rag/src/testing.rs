@@ -0,0 +1,202 @@
+//! In-memory stand-ins for the index and the embedding model.
+
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use domain::{
+    chunk::Chunk,
+    ids::{ChunkId, ObservationId},
+    observation::SourceKind,
+    search::{ObservationSummary, SearchFilters, SearchHit},
+};
+use embedding::Embedder;
+
+use crate::{
+    error::{RerankError, Result},
+    index::ChunkIndex,
+    rerank::Reranker,
+};
+
+pub fn hit(observation_id: ObservationId, text: &str, start: usize, score: f32) -> SearchHit {
+    SearchHit {
+        chunk: Chunk::reconstruct(
+            ChunkId::new(),
+            observation_id,
+            0,
+            text.to_string(),
+            start,
+            start + text.len(),
+            1,
+        ),
+        observation: ObservationSummary {
+            id: observation_id,
+            title: None,
+            source_url: None,
+            source_kind: SourceKind::Text,
+            created_at: Utc::now(),
+            published_at: None,
+        },
+        score,
+        snippet: None,
+    }
+}
+
+/// Returns fixed result lists and records the vector queries it gets.
+pub struct FakeIndex {
+    pub keyword: Vec<SearchHit>,
+    pub vector: Vec<SearchHit>,
+    pub vector_queries: Mutex<Vec<(String, Vec<f32>)>>,
+}
+
+impl FakeIndex {
+    pub const fn new(keyword: Vec<SearchHit>, vector: Vec<SearchHit>) -> Self {
+        Self {
+            keyword,
+            vector,
+            vector_queries: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl ChunkIndex for FakeIndex {
+    async fn keyword_search(
+        &self,
+        _query: &str,
+        k: usize,
+        _filters: &SearchFilters,
+    ) -> Result<Vec<SearchHit>> {
+        Ok(self.keyword.iter().take(k).cloned().collect())
+    }
+
+    async fn vector_search(
+        &self,
+        model: &str,
+        vector: &[f32],
+        k: usize,
+        _filters: &SearchFilters,
+    ) -> Result<Vec<SearchHit>> {
+        self.vector_queries
+            .lock()
+            .unwrap()
+            .push((model.to_string(), vector.to_vec()));
+        Ok(self.vector.iter().take(k).cloned().collect())
+    }
+
+    async fn embeddings(
+        &self,
+        _model: &str,
+        _chunk_ids: &[ChunkId],
+    ) -> Result<Vec<(ChunkId, Vec<f32>)>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Searches chunks held in memory: keyword search ranks by how many query
+/// words a chunk contains, vector search by cosine similarity to vectors
+/// from one embedder.
+pub struct MemoryIndex {
+    chunks: Vec<(SearchHit, Vec<f32>)>,
+}
+
+impl MemoryIndex {
+    /// Embeds each text as a chunk of its own observation.
+    pub async fn embed(embedder: &dyn Embedder, texts: &[&str]) -> Self {
+        let vectors = embedder.embed(texts).await.unwrap();
+        let chunks = texts
+            .iter()
+            .zip(vectors)
+            .map(|(text, vector)| (hit(ObservationId::new(), text, 0, 0.0), vector))
+            .collect();
+        Self { chunks }
+    }
+
+    fn ranked(&self, k: usize, score: impl Fn(&SearchHit, &[f32]) -> f32) -> Vec<SearchHit> {
+        let mut hits: Vec<SearchHit> = self
+            .chunks
+            .iter()
+            .map(|(hit, vector)| SearchHit {
+                score: score(hit, vector),
+                ..hit.clone()
+            })
+            .filter(|hit| hit.score > 0.0)
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(k);
+        hits
+    }
+}
+
+#[async_trait]
+impl ChunkIndex for MemoryIndex {
+    async fn keyword_search(
+        &self,
+        query: &str,
+        k: usize,
+        _filters: &SearchFilters,
+    ) -> Result<Vec<SearchHit>> {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        Ok(self.ranked(k, |hit, _| {
+            let text = hit.chunk.text().to_lowercase();
+            words.iter().filter(|w| text.contains(w.as_str())).count() as f32
+        }))
+    }
+
+    async fn vector_search(
+        &self,
+        _model: &str,
+        vector: &[f32],
+        k: usize,
+        _filters: &SearchFilters,
+    ) -> Result<Vec<SearchHit>> {
+        Ok(self.ranked(k, |_, other| {
+            vector.iter().zip(other).map(|(a, b)| a * b).sum()
+        }))
+    }
+
+    async fn embeddings(
+        &self,
+        _model: &str,
+        chunk_ids: &[ChunkId],
+    ) -> Result<Vec<(ChunkId, Vec<f32>)>> {
+        Ok(self
+            .chunks
+            .iter()
+            .filter(|(hit, _)| chunk_ids.contains(&hit.chunk.id()))
+            .map(|(hit, vector)| (hit.chunk.id(), vector.clone()))
+            .collect())
+    }
+}
+
+/// Embeds each text as its length.
+pub struct LengthEmbedder;
+
+#[async_trait]
+impl Embedder for LengthEmbedder {
+    fn model_id(&self) -> &str {
+        "length"
+    }
+
+    fn dimension(&self) -> usize {
+        1
+    }
+
+    async fn embed(&self, texts: &[&str]) -> embedding::Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|t| vec![t.len() as f32]).collect())
+    }
+}
+
+/// Scores each document by its length, so longer texts rank higher.
+pub struct LengthReranker;
+
+#[async_trait]
+impl Reranker for LengthReranker {
+    async fn score(
+        &self,
+        _query: &str,
+        documents: &[&str],
+    ) -> std::result::Result<Vec<f32>, RerankError> {
+        Ok(documents.iter().map(|d| d.len() as f32).collect())
+    }
+}
@@ -0,0 +1,25 @@
+CREATE TABLE IF NOT EXISTS feeds (
+    id UUID PRIMARY KEY,
+    url TEXT NOT NULL UNIQUE,
+    title TEXT,
+    poll_interval_secs BIGINT NOT NULL CHECK (poll_interval_secs > 0),
+    etag TEXT,
+    last_modified TEXT,
+    last_polled_at TIMESTAMPTZ,
+    next_poll_at TIMESTAMPTZ,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS feeds_next_poll_at_idx ON feeds (next_poll_at);
+
+CREATE TABLE IF NOT EXISTS feed_polls (
+    id BIGSERIAL PRIMARY KEY,
+    feed_id UUID NOT NULL REFERENCES feeds (id) ON DELETE CASCADE,
+    polled_at TIMESTAMPTZ NOT NULL,
+    status TEXT NOT NULL,
+    new_observations INTEGER NOT NULL DEFAULT 0,
+    error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS feed_polls_feed_id_idx ON feed_polls (feed_id, polled_at DESC);
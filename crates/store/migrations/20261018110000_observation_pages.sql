@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS observation_pages (
+    observation_id UUID NOT NULL REFERENCES observations (id) ON DELETE CASCADE,
+    page_number INTEGER NOT NULL,
+    start_offset BIGINT NOT NULL,
+    end_offset BIGINT NOT NULL,
+    PRIMARY KEY (observation_id, page_number)
+);
@@ -0,0 +1 @@
+ALTER TABLE chunks ADD COLUMN IF NOT EXISTS heading_path TEXT[] NOT NULL DEFAULT '{}';
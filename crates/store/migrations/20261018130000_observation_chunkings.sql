@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS observation_chunkings (
+    observation_id UUID PRIMARY KEY REFERENCES observations (id) ON DELETE CASCADE,
+    strategy TEXT NOT NULL,
+    size_unit TEXT NOT NULL,
+    size_limit BIGINT NOT NULL,
+    overlap BIGINT NOT NULL,
+    chunk_count INTEGER NOT NULL,
+    chunked_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,17 @@
+CREATE EXTENSION IF NOT EXISTS vector;
+
+-- The embedding column has no fixed dimension so that vectors of several
+-- models can live side by side. HNSW indexes need one, so each model gets
+-- its own partial index on `embedding::vector(<dimension>)`, created by
+-- the store when the model's first embeddings are written.
+CREATE TABLE IF NOT EXISTS chunk_embeddings (
+    chunk_id UUID NOT NULL REFERENCES chunks (id) ON DELETE CASCADE,
+    model TEXT NOT NULL,
+    dimension INTEGER NOT NULL,
+    embedding vector NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (chunk_id, model),
+    CHECK (vector_dims(embedding) = dimension)
+);
+
+CREATE INDEX IF NOT EXISTS chunk_embeddings_model_idx ON chunk_embeddings (model);
@@ -0,0 +1,16 @@
+-- Each row is indexed with the text search configuration matching its
+-- observation's language ('simple' when unknown); chunks copy the
+-- configuration of their observation when they are stored.
+ALTER TABLE observations ADD COLUMN IF NOT EXISTS language TEXT;
+ALTER TABLE observations ADD COLUMN IF NOT EXISTS search_config regconfig NOT NULL DEFAULT 'simple';
+ALTER TABLE observations ADD COLUMN IF NOT EXISTS title_tsv tsvector
+    GENERATED ALWAYS AS (to_tsvector(search_config, coalesce(title, ''))) STORED;
+
+CREATE INDEX IF NOT EXISTS observations_search_config_idx ON observations (search_config);
+CREATE INDEX IF NOT EXISTS observations_title_tsv_idx ON observations USING GIN (title_tsv);
+
+ALTER TABLE chunks ADD COLUMN IF NOT EXISTS search_config regconfig NOT NULL DEFAULT 'simple';
+ALTER TABLE chunks ADD COLUMN IF NOT EXISTS text_tsv tsvector
+    GENERATED ALWAYS AS (to_tsvector(search_config, text)) STORED;
+
+CREATE INDEX IF NOT EXISTS chunks_text_tsv_idx ON chunks USING GIN (text_tsv);
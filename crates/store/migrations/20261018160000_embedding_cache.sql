@@ -0,0 +1,24 @@
+-- Embeddings by the text they were made from rather than by chunk, so a
+-- text that appears in several chunks, or again after re-chunking, is
+-- embedded once per model. `text_hash` is the hex SHA-256 of the UTF-8
+-- text, the same digest `ContentHash` produces.
+CREATE TABLE IF NOT EXISTS embedding_cache (
+    model TEXT NOT NULL,
+    text_hash TEXT NOT NULL,
+    dimension INTEGER NOT NULL,
+    embedding vector NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (model, text_hash),
+    CHECK (vector_dims(embedding) = dimension)
+);
+
+INSERT INTO embedding_cache (model, text_hash, dimension, embedding, created_at)
+SELECT DISTINCT ON (e.model, encode(sha256(convert_to(c.text, 'UTF8')), 'hex'))
+    e.model,
+    encode(sha256(convert_to(c.text, 'UTF8')), 'hex'),
+    e.dimension,
+    e.embedding,
+    e.created_at
+FROM chunk_embeddings e
+JOIN chunks c ON c.id = e.chunk_id
+ON CONFLICT (model, text_hash) DO NOTHING;
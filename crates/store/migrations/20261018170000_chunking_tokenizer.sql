@@ -0,0 +1,3 @@
+-- Token-sized chunkings depend on the tokenizer that counted them. Rows
+-- recorded before it was tracked have none, so token-sized ones are stale.
+ALTER TABLE observation_chunkings ADD COLUMN IF NOT EXISTS tokenizer TEXT;
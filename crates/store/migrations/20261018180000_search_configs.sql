@@ -0,0 +1,11 @@
+-- Text search configurations used by at least one observation, so keyword
+-- search parses its query once per configuration without scanning
+-- observations. Rows are added as observations are stored and never
+-- removed; a configuration nothing uses any more just matches no chunks.
+CREATE TABLE IF NOT EXISTS search_configs (
+    search_config regconfig PRIMARY KEY
+);
+
+INSERT INTO search_configs (search_config)
+SELECT DISTINCT search_config FROM observations
+ON CONFLICT (search_config) DO NOTHING;
@@ -15,4 +15,30 @@ pub enum StoreError {
 
     #[error("value out of range: {0}")]
     OutOfRange(&'static str),
+
+    #[error("embedding for model {model} has {actual} dimensions, expected {expected}")]
+    DimensionMismatch {
+        model: String,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("invalid embedding: {0}")]
+    InvalidEmbedding(&'static str),
+}
+
+impl StoreError {
+    /// Whether the same statement may succeed if run again later: the
+    /// connection dropped, the pool was exhausted, or the server gave up on
+    /// a serialization conflict, deadlock or shutdown.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Sqlx(sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut) => true,
+            Self::Sqlx(sqlx::Error::Database(err)) => err.code().is_some_and(|code| {
+                code.starts_with("08") || matches!(&*code, "40001" | "40P01" | "53300" | "57P01")
+            }),
+            _ => false,
+        }
+    }
 }
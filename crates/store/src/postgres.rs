@@ -1,10 +1,20 @@
 use chrono::{DateTime, Utc};
+use std::time::Duration;
+
 use domain::{
     chunk::Chunk,
-    ids::{ChunkId, ObservationId},
+    chunker::ChunkOptions,
+    feed::{Feed, FeedPollState, FeedPollStatus},
+    ids::{ChunkId, ContentHash, FeedId, ObservationId},
     observation::{Observation, SourceKind},
+    page::{PageMap, PageSpan},
+    search::{DateField, ObservationSummary, SearchFilters, SearchHit},
+};
+use sqlx::{
+    PgPool, Postgres, Row,
+    postgres::{PgArguments, PgPoolOptions, PgRow},
+    query::Query,
 };
-use sqlx::{PgPool, Row, postgres::PgPoolOptions};
 use uuid::Uuid;
 
 use crate::error::{Result, StoreError};
@@ -40,6 +50,7 @@ impl PgStore {
         observation: &Observation,
     ) -> Result<(ObservationId, bool)> {
         let content_hash = observation.content_hash().to_hex();
+        let mut tx = self.pool.begin().await?;
 
         let inserted_id: Option<Uuid> = sqlx::query_scalar(
             r#"
@@ -51,9 +62,11 @@ INSERT INTO observations (
     source_url,
     source_kind,
     created_at,
-    published_at
+    published_at,
+    language,
+    search_config
 )
-VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10::regconfig)
 ON CONFLICT (content_hash) DO NOTHING
 RETURNING id
             "#,
@@ -66,22 +79,97 @@ RETURNING id
         .bind(observation.source_kind().as_str())
         .bind(observation.created_at())
         .bind(observation.published_at())
-        .fetch_optional(&self.pool)
+        .bind(observation.language())
+        .bind(search_config(observation.language()))
+        .fetch_optional(&mut *tx)
         .await?;
 
         if let Some(id) = inserted_id {
+            sqlx::query(
+                r#"
+INSERT INTO search_configs (search_config)
+VALUES ($1::regconfig)
+ON CONFLICT (search_config) DO NOTHING
+                "#,
+            )
+            .bind(search_config(observation.language()))
+            .execute(&mut *tx)
+            .await?;
+
+            if let Some(page_map) = observation.page_map() {
+                for span in page_map.spans() {
+                    let page_number = i32::try_from(span.number)
+                        .map_err(|_| StoreError::OutOfRange("page_number"))?;
+                    let start_offset = i64::try_from(span.start_offset)
+                        .map_err(|_| StoreError::OutOfRange("start_offset"))?;
+                    let end_offset = i64::try_from(span.end_offset)
+                        .map_err(|_| StoreError::OutOfRange("end_offset"))?;
+
+                    sqlx::query(
+                        r#"
+INSERT INTO observation_pages (observation_id, page_number, start_offset, end_offset)
+VALUES ($1, $2, $3, $4)
+                        "#,
+                    )
+                    .bind(id)
+                    .bind(page_number)
+                    .bind(start_offset)
+                    .bind(end_offset)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+
+            tx.commit().await?;
             return Ok((ObservationId::from_raw(id), true));
         }
 
         let existing_id: Uuid =
             sqlx::query_scalar("SELECT id FROM observations WHERE content_hash = $1")
                 .bind(&content_hash)
-                .fetch_one(&self.pool)
+                .fetch_one(&mut *tx)
                 .await?;
 
+        tx.commit().await?;
         Ok((ObservationId::from_raw(existing_id), false))
     }
 
+    async fn get_page_map(&self, id: ObservationId) -> Result<Option<PageMap>> {
+        let rows = sqlx::query(
+            r#"
+SELECT page_number, start_offset, end_offset
+FROM observation_pages
+WHERE observation_id = $1
+ORDER BY page_number ASC
+            "#,
+        )
+        .bind(id.into_inner())
+        .fetch_all(&self.pool)
+        .await?;
+
+        if rows.is_empty() {
+            return Ok(None);
+        }
+
+        let mut spans = Vec::with_capacity(rows.len());
+        for row in rows {
+            let page_number: i32 = row.try_get("page_number")?;
+            let start_offset: i64 = row.try_get("start_offset")?;
+            let end_offset: i64 = row.try_get("end_offset")?;
+
+            spans.push(PageSpan {
+                number: u32::try_from(page_number)
+                    .map_err(|_| StoreError::OutOfRange("page_number"))?,
+                start_offset: usize::try_from(start_offset)
+                    .map_err(|_| StoreError::OutOfRange("start_offset"))?,
+                end_offset: usize::try_from(end_offset)
+                    .map_err(|_| StoreError::OutOfRange("end_offset"))?,
+            });
+        }
+
+        Ok(Some(PageMap::new(spans)?))
+    }
+
     pub async fn get_observation(&self, id: ObservationId) -> Result<Option<Observation>> {
         let row = sqlx::query(
             r#"
@@ -92,7 +180,8 @@ SELECT
     source_url,
     source_kind,
     created_at,
-    published_at
+    published_at,
+    language
 FROM observations
 WHERE id = $1
             "#,
@@ -112,6 +201,7 @@ WHERE id = $1
         let source_kind: String = row.try_get("source_kind")?;
         let created_at: DateTime<Utc> = row.try_get("created_at")?;
         let published_at: Option<DateTime<Utc>> = row.try_get("published_at")?;
+        let language: Option<String> = row.try_get("language")?;
 
         let mut builder = Observation::builder()
             .with_id(ObservationId::from_raw(id))
@@ -131,12 +221,50 @@ WHERE id = $1
             builder = builder.published_at(published_at);
         }
 
+        if let Some(language) = language {
+            builder = builder.language(language);
+        }
+
+        if let Some(page_map) = self.get_page_map(ObservationId::from_raw(id)).await? {
+            builder = builder.page_map(page_map);
+        }
+
         Ok(Some(builder.build()?))
     }
 
-    pub async fn upsert_chunks(&self, chunks: &[Chunk]) -> Result<u64> {
+    /// Replaces the whole chunk set of an observation in one transaction
+    /// and records the options and tokenizer that produced it, so a
+    /// re-chunking never leaves chunks of an earlier one behind.
+    ///
+    /// The old chunks are deleted along with their embeddings, even where
+    /// the new chunks have the same text.
+    pub async fn replace_chunks(
+        &self,
+        observation_id: ObservationId,
+        options: &ChunkOptions,
+        tokenizer: &str,
+        chunks: &[Chunk],
+    ) -> Result<()> {
+        let size_limit = i64::try_from(options.size.limit())
+            .map_err(|_| StoreError::OutOfRange("size_limit"))?;
+        let overlap =
+            i64::try_from(options.overlap).map_err(|_| StoreError::OutOfRange("overlap"))?;
+        let chunk_count =
+            i32::try_from(chunks.len()).map_err(|_| StoreError::OutOfRange("chunk_count"))?;
+
         let mut tx = self.pool.begin().await?;
-        let mut affected_total = 0u64;
+
+        // Concurrent replacements of one observation would interleave their
+        // deletes and inserts; locking the observation row serializes them.
+        sqlx::query("SELECT 1 FROM observations WHERE id = $1 FOR UPDATE")
+            .bind(observation_id.into_inner())
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM chunks WHERE observation_id = $1")
+            .bind(observation_id.into_inner())
+            .execute(&mut *tx)
+            .await?;
 
         for chunk in chunks {
             let start_offset = i64::try_from(chunk.start_offset())
@@ -146,7 +274,7 @@ WHERE id = $1
             let token_estimate = i32::try_from(chunk.token_estimate())
                 .map_err(|_| StoreError::OutOfRange("token_estimate"))?;
 
-            let result = sqlx::query(
+            sqlx::query(
                 r#"
 INSERT INTO chunks (
     id,
@@ -155,36 +283,743 @@ INSERT INTO chunks (
     text,
     start_offset,
     end_offset,
-    token_estimate
+    token_estimate,
+    heading_path,
+    search_config
 )
-VALUES ($1, $2, $3, $4, $5, $6, $7)
-ON CONFLICT (observation_id, chunk_index) DO UPDATE SET
-    text = EXCLUDED.text,
-    start_offset = EXCLUDED.start_offset,
-    end_offset = EXCLUDED.end_offset,
-    token_estimate = EXCLUDED.token_estimate
+SELECT $1, $2, $3, $4, $5, $6, $7, $8, search_config
+FROM observations
+WHERE id = $2
                 "#,
             )
             .bind(chunk.id().into_inner())
-            .bind(chunk.observation_id().into_inner())
+            .bind(observation_id.into_inner())
             .bind(chunk.index())
             .bind(chunk.text())
             .bind(start_offset)
             .bind(end_offset)
             .bind(token_estimate)
+            .bind(chunk.heading_path())
             .execute(&mut *tx)
             .await?;
-
-            affected_total += result.rows_affected();
         }
 
+        sqlx::query(
+            r#"
+INSERT INTO observation_chunkings (
+    observation_id,
+    strategy,
+    size_unit,
+    size_limit,
+    overlap,
+    tokenizer,
+    chunk_count,
+    chunked_at
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+ON CONFLICT (observation_id) DO UPDATE SET
+    strategy = EXCLUDED.strategy,
+    size_unit = EXCLUDED.size_unit,
+    size_limit = EXCLUDED.size_limit,
+    overlap = EXCLUDED.overlap,
+    tokenizer = EXCLUDED.tokenizer,
+    chunk_count = EXCLUDED.chunk_count,
+    chunked_at = EXCLUDED.chunked_at
+            "#,
+        )
+        .bind(observation_id.into_inner())
+        .bind(options.strategy.as_str())
+        .bind(options.size.unit())
+        .bind(size_limit)
+        .bind(overlap)
+        .bind(tokenizer)
+        .bind(chunk_count)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+
         tx.commit().await?;
-        Ok(affected_total)
+        Ok(())
+    }
+
+    /// Observations whose chunks were produced with options other than
+    /// `options`, including chunks stored before options were recorded.
+    /// Token-sized chunks are also stale when they were counted with
+    /// another tokenizer. Observations that were never chunked are not
+    /// included.
+    pub async fn stale_chunkings(
+        &self,
+        options: &ChunkOptions,
+        tokenizer: &str,
+    ) -> Result<Vec<ObservationId>> {
+        let size_limit = i64::try_from(options.size.limit())
+            .map_err(|_| StoreError::OutOfRange("size_limit"))?;
+        let overlap =
+            i64::try_from(options.overlap).map_err(|_| StoreError::OutOfRange("overlap"))?;
+
+        let ids: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+SELECT o.id
+FROM observations o
+LEFT JOIN observation_chunkings c ON c.observation_id = o.id
+WHERE
+    (c.observation_id IS NULL AND EXISTS (SELECT 1 FROM chunks WHERE observation_id = o.id))
+    OR (
+        c.observation_id IS NOT NULL
+        AND (
+            c.strategy,
+            c.size_unit,
+            c.size_limit,
+            c.overlap,
+            CASE WHEN c.size_unit = 'tokens' THEN c.tokenizer END
+        ) IS DISTINCT FROM ($1, $2, $3, $4, CASE WHEN $2 = 'tokens' THEN $5 END)
+    )
+ORDER BY o.created_at ASC
+            "#,
+        )
+        .bind(options.strategy.as_str())
+        .bind(options.size.unit())
+        .bind(size_limit)
+        .bind(overlap)
+        .bind(tokenizer)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids.into_iter().map(ObservationId::from_raw).collect())
     }
 
     pub async fn list_chunks(&self, observation_id: ObservationId) -> Result<Vec<Chunk>> {
-        let rows = sqlx::query(
+        let rows = sqlx::query(&format!(
+            "{CHUNK_SELECT} WHERE observation_id = $1 ORDER BY chunk_index ASC"
+        ))
+        .bind(observation_id.into_inner())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(chunk_from_row).collect()
+    }
+
+    /// Stores embeddings made by `model`, replacing any the chunks already
+    /// have for it. Chunks deleted since they were embedded are skipped.
+    /// Returns how many embeddings were written.
+    ///
+    /// All vectors of a model must have the same dimension; the first batch
+    /// fixes it and creates the model's HNSW index.
+    pub async fn upsert_embeddings(
+        &self,
+        model: &str,
+        embeddings: &[(ChunkId, Vec<f32>)],
+    ) -> Result<u64> {
+        let Some((_, first)) = embeddings.first() else {
+            return Ok(0);
+        };
+        let dimension = first.len();
+
+        let mut chunk_ids = Vec::with_capacity(embeddings.len());
+        let mut vectors = Vec::with_capacity(embeddings.len());
+        for (chunk_id, vector) in embeddings {
+            if vector.len() != dimension {
+                return Err(StoreError::DimensionMismatch {
+                    model: model.to_string(),
+                    expected: dimension,
+                    actual: vector.len(),
+                });
+            }
+            chunk_ids.push(chunk_id.into_inner());
+            vectors.push(vector_literal(vector)?);
+        }
+
+        self.prepare_embedding_model(model, dimension).await?;
+        let dimension =
+            i32::try_from(dimension).map_err(|_| StoreError::OutOfRange("dimension"))?;
+
+        let result = sqlx::query(
+            r#"
+INSERT INTO chunk_embeddings (chunk_id, model, dimension, embedding, created_at)
+SELECT t.chunk_id, $2, $3, t.embedding::vector, $4
+FROM UNNEST($1::uuid[], $5::text[]) AS t (chunk_id, embedding)
+JOIN chunks c ON c.id = t.chunk_id
+ON CONFLICT (chunk_id, model) DO UPDATE SET
+    dimension = EXCLUDED.dimension,
+    embedding = EXCLUDED.embedding,
+    created_at = EXCLUDED.created_at
+            "#,
+        )
+        .bind(&chunk_ids)
+        .bind(model)
+        .bind(dimension)
+        .bind(Utc::now())
+        .bind(&vectors)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Checks `dimension` against the vectors already stored for `model`
+    /// and creates the model's HNSW index if it does not exist yet.
+    async fn prepare_embedding_model(&self, model: &str, dimension: usize) -> Result<()> {
+        if dimension == 0 || dimension > MAX_INDEXED_DIMENSION {
+            return Err(StoreError::OutOfRange("dimension"));
+        }
+
+        if let Some(stored) = self.embedding_dimension(model).await?
+            && stored != dimension
+        {
+            return Err(StoreError::DimensionMismatch {
+                model: model.to_string(),
+                expected: stored,
+                actual: dimension,
+            });
+        }
+
+        let index = embedding_index_name(model);
+        let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(&index)
+            .fetch_one(&self.pool)
+            .await?;
+        if exists {
+            return Ok(());
+        }
+
+        // Concurrent first batches of a model would race to create the
+        // index; a transaction-scoped lock on its name serializes them.
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(&index)
+            .execute(&mut *tx)
+            .await?;
+
+        // Index definitions cannot take bind parameters, so the model name
+        // is inlined as an escaped literal.
+        sqlx::query(&format!(
+            r#"
+CREATE INDEX IF NOT EXISTS {index} ON chunk_embeddings
+USING hnsw ((embedding::vector({dimension})) vector_cosine_ops)
+WHERE model = {}
+            "#,
+            sql_literal(model),
+        ))
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Gives chunks the `model` embeddings cached for their texts, keyed by
+    /// each chunk's text hash. Only cached vectors of `dimension` are used.
+    /// Returns the chunks that were found in the cache.
+    pub async fn embed_from_cache(
+        &self,
+        model: &str,
+        dimension: usize,
+        chunks: &[(ChunkId, ContentHash)],
+    ) -> Result<Vec<ChunkId>> {
+        if chunks.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.prepare_embedding_model(model, dimension).await?;
+        let dimension =
+            i32::try_from(dimension).map_err(|_| StoreError::OutOfRange("dimension"))?;
+
+        let (chunk_ids, hashes): (Vec<Uuid>, Vec<String>) = chunks
+            .iter()
+            .map(|(id, hash)| (id.into_inner(), hash.to_hex()))
+            .unzip();
+
+        let rows: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+INSERT INTO chunk_embeddings (chunk_id, model, dimension, embedding, created_at)
+SELECT t.chunk_id, cache.model, cache.dimension, cache.embedding, $4
+FROM UNNEST($1::uuid[], $2::text[]) AS t (chunk_id, text_hash)
+JOIN chunks c ON c.id = t.chunk_id
+JOIN embedding_cache cache ON cache.model = $3 AND cache.text_hash = t.text_hash
+WHERE cache.dimension = $5
+ON CONFLICT (chunk_id, model) DO UPDATE SET
+    dimension = EXCLUDED.dimension,
+    embedding = EXCLUDED.embedding,
+    created_at = EXCLUDED.created_at
+RETURNING chunk_id
+            "#,
+        )
+        .bind(&chunk_ids)
+        .bind(&hashes)
+        .bind(model)
+        .bind(Utc::now())
+        .bind(dimension)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(ChunkId::from_raw).collect())
+    }
+
+    /// Caches embeddings made by `model` under the hashes of the texts they
+    /// were made from. Texts already cached keep their first embedding.
+    pub async fn cache_embeddings(
+        &self,
+        model: &str,
+        embeddings: &[(ContentHash, Vec<f32>)],
+    ) -> Result<u64> {
+        let Some((_, first)) = embeddings.first() else {
+            return Ok(0);
+        };
+        let dimension = first.len();
+
+        let mut hashes = Vec::with_capacity(embeddings.len());
+        let mut vectors = Vec::with_capacity(embeddings.len());
+        for (hash, vector) in embeddings {
+            if vector.len() != dimension {
+                return Err(StoreError::DimensionMismatch {
+                    model: model.to_string(),
+                    expected: dimension,
+                    actual: vector.len(),
+                });
+            }
+            hashes.push(hash.to_hex());
+            vectors.push(vector_literal(vector)?);
+        }
+        let dimension =
+            i32::try_from(dimension).map_err(|_| StoreError::OutOfRange("dimension"))?;
+
+        let result = sqlx::query(
+            r#"
+INSERT INTO embedding_cache (model, text_hash, dimension, embedding, created_at)
+SELECT $2, t.text_hash, $3, t.embedding::vector, $4
+FROM UNNEST($1::text[], $5::text[]) AS t (text_hash, embedding)
+ON CONFLICT (model, text_hash) DO NOTHING
+            "#,
+        )
+        .bind(&hashes)
+        .bind(model)
+        .bind(dimension)
+        .bind(Utc::now())
+        .bind(&vectors)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Dimension of the vectors stored for `model`, if it has any.
+    pub async fn embedding_dimension(&self, model: &str) -> Result<Option<usize>> {
+        let dimension: Option<i32> =
+            sqlx::query_scalar("SELECT dimension FROM chunk_embeddings WHERE model = $1 LIMIT 1")
+                .bind(model)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        dimension
+            .map(|d| usize::try_from(d).map_err(|_| StoreError::OutOfRange("dimension")))
+            .transpose()
+    }
+
+    /// The `model` embeddings of the given chunks. Chunks without one are
+    /// left out.
+    pub async fn embeddings(
+        &self,
+        model: &str,
+        chunk_ids: &[ChunkId],
+    ) -> Result<Vec<(ChunkId, Vec<f32>)>> {
+        if chunk_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids: Vec<Uuid> = chunk_ids.iter().copied().map(ChunkId::into_inner).collect();
+
+        let rows: Vec<(Uuid, String)> = sqlx::query_as(
+            r#"
+SELECT chunk_id, embedding::text
+FROM chunk_embeddings
+WHERE model = $1 AND chunk_id = ANY($2)
+            "#,
+        )
+        .bind(model)
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(id, literal)| Ok((ChunkId::from_raw(id), parse_vector(&literal)?)))
+            .collect()
+    }
+
+    /// The `k` chunks whose `model` embeddings are closest to `vector` by
+    /// cosine distance, best first. The score is the cosine similarity.
+    ///
+    /// Filters are applied to the index scan's candidates. pgvector 0.8 and
+    /// later keep scanning until `k` chunks pass them; older versions
+    /// consider as many candidates as they allow when filters are set, but
+    /// a selective filter can still leave fewer than `k` even when more
+    /// chunks match.
+    pub async fn search_similar(
+        &self,
+        model: &str,
+        vector: &[f32],
+        k: usize,
+        filters: &SearchFilters,
+    ) -> Result<Vec<SearchHit>> {
+        let Some(dimension) = self.embedding_dimension(model).await? else {
+            return Ok(Vec::new());
+        };
+        if vector.len() != dimension {
+            return Err(StoreError::DimensionMismatch {
+                model: model.to_string(),
+                expected: dimension,
+                actual: vector.len(),
+            });
+        }
+        let limit = i64::try_from(k).map_err(|_| StoreError::OutOfRange("k"))?;
+
+        let mut tx = self.pool.begin().await?;
+
+        // HNSW only considers `ef_search` candidates, which caps the number
+        // of rows one scan can return before filters are applied. From
+        // pgvector 0.8 the scan can keep going until enough rows pass the
+        // filters, in roughly distance order; the outer query re-sorts them.
+        sqlx::query(
             r#"
+SELECT
+    set_config('hnsw.ef_search', $1, true),
+    (
+        SELECT set_config('hnsw.iterative_scan', 'relaxed_order', true)
+        FROM pg_extension
+        WHERE extname = 'vector' AND string_to_array(extversion, '.')::int[] >= '{0,8}'
+    )
+            "#,
+        )
+        .bind(if filters.is_empty() {
+            k.clamp(MIN_EF_SEARCH, MAX_EF_SEARCH).to_string()
+        } else {
+            MAX_EF_SEARCH.to_string()
+        })
+        .execute(&mut *tx)
+        .await?;
+
+        // The distance expression and the model predicate must match the
+        // model's index definition for the planner to use it, so the model
+        // is inlined as the same literal rather than bound.
+        let query = format!(
+            r#"
+WITH nearest AS MATERIALIZED (
+SELECT
+    c.id,
+    c.observation_id,
+    c.chunk_index,
+    c.text,
+    c.start_offset,
+    c.end_offset,
+    c.token_estimate,
+    c.heading_path,
+    o.title,
+    o.source_url,
+    o.source_kind,
+    o.created_at,
+    o.published_at,
+    e.embedding::vector({dimension}) <=> $1::vector({dimension}) AS distance
+FROM chunk_embeddings e
+JOIN chunks c ON c.id = e.chunk_id
+JOIN observations o ON o.id = c.observation_id
+WHERE e.model = {model} AND {conditions}
+ORDER BY distance
+LIMIT $2
+)
+SELECT *, (1 - distance)::real AS score, NULL::text AS snippet
+FROM nearest
+ORDER BY distance
+            "#,
+            model = sql_literal(model),
+            conditions = filter_conditions(filters, 3),
+        );
+        let rows = bind_filters(
+            sqlx::query(&query)
+                .bind(vector_literal(vector)?)
+                .bind(limit),
+            filters,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        rows.iter().map(search_hit_from_row).collect()
+    }
+
+    /// The `k` chunks best matching `query`, a web-search style query
+    /// (`"quoted phrases"`, `or`, `-excluded`), best first. Chunk text is
+    /// matched with the text search configuration of its observation's
+    /// language and observation titles boost the rank. Each hit carries a
+    /// snippet with the matched terms highlighted.
+    pub async fn keyword_search(
+        &self,
+        query: &str,
+        k: usize,
+        filters: &SearchFilters,
+    ) -> Result<Vec<SearchHit>> {
+        let limit = i64::try_from(k).map_err(|_| StoreError::OutOfRange("k"))?;
+
+        // The query is parsed once per configuration in use, so every chunk
+        // is matched against a query stemmed the same way as its text.
+        let query_sql = format!(
+            r#"
+WITH queries AS (
+    SELECT search_config, websearch_to_tsquery(search_config, $1) AS query
+    FROM search_configs
+),
+ranked AS (
+    SELECT
+        c.id AS chunk_id,
+        q.search_config,
+        q.query,
+        (ts_rank_cd(c.text_tsv, q.query, 32) + 0.5 * ts_rank_cd(o.title_tsv, q.query, 32))::real
+            AS score
+    FROM queries q
+    JOIN chunks c ON c.search_config = q.search_config AND c.text_tsv @@ q.query
+    JOIN observations o ON o.id = c.observation_id
+    WHERE {conditions}
+    ORDER BY score DESC, c.id ASC
+    LIMIT $2
+)
+SELECT
+    c.id,
+    c.observation_id,
+    c.chunk_index,
+    c.text,
+    c.start_offset,
+    c.end_offset,
+    c.token_estimate,
+    c.heading_path,
+    o.title,
+    o.source_url,
+    o.source_kind,
+    o.created_at,
+    o.published_at,
+    r.score,
+    ts_headline(r.search_config, c.text, r.query, $3) AS snippet
+FROM ranked r
+JOIN chunks c ON c.id = r.chunk_id
+JOIN observations o ON o.id = c.observation_id
+ORDER BY r.score DESC, c.id ASC
+            "#,
+            conditions = filter_conditions(filters, 4),
+        );
+        let rows = bind_filters(
+            sqlx::query(&query_sql)
+                .bind(query)
+                .bind(limit)
+                .bind(HEADLINE_OPTIONS),
+            filters,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(search_hit_from_row).collect()
+    }
+
+    /// Chunks that have no embedding from `model` yet, in id order, starting
+    /// after the chunk `after`. Passing the last id of one page as `after`
+    /// for the next pages through the backlog without revisiting chunks.
+    pub async fn chunks_missing_embeddings(
+        &self,
+        model: &str,
+        after: Option<ChunkId>,
+        limit: usize,
+    ) -> Result<Vec<Chunk>> {
+        let limit = i64::try_from(limit).map_err(|_| StoreError::OutOfRange("limit"))?;
+
+        let rows = sqlx::query(&format!(
+            r#"
+{CHUNK_SELECT}
+WHERE ($2::uuid IS NULL OR id > $2)
+  AND NOT EXISTS (
+    SELECT 1 FROM chunk_embeddings e WHERE e.chunk_id = chunks.id AND e.model = $1
+  )
+ORDER BY id ASC
+LIMIT $3
+            "#
+        ))
+        .bind(model)
+        .bind(after.map(ChunkId::into_inner))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(chunk_from_row).collect()
+    }
+
+    /// How many chunks have an embedding from `model`, and how many do not.
+    pub async fn embedding_counts(&self, model: &str) -> Result<(u64, u64)> {
+        let (embedded, missing): (i64, i64) = sqlx::query_as(
+            r#"
+SELECT
+    (SELECT count(*) FROM chunk_embeddings WHERE model = $1),
+    (
+        SELECT count(*) FROM chunks c
+        WHERE NOT EXISTS (
+            SELECT 1 FROM chunk_embeddings e WHERE e.chunk_id = c.id AND e.model = $1
+        )
+    )
+            "#,
+        )
+        .bind(model)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let count = |n: i64| u64::try_from(n).map_err(|_| StoreError::OutOfRange("count"));
+        Ok((count(embedded)?, count(missing)?))
+    }
+
+    /// Subscribes to a feed. Returns the existing subscription's id and
+    /// `false` if the URL is already subscribed.
+    pub async fn add_feed(&self, feed: &Feed) -> Result<(FeedId, bool)> {
+        let poll_interval_secs = i64::try_from(feed.poll_interval().as_secs())
+            .map_err(|_| StoreError::OutOfRange("poll_interval_secs"))?;
+        let state = feed.state();
+
+        let inserted_id: Option<Uuid> = sqlx::query_scalar(
+            r#"
+INSERT INTO feeds (
+    id,
+    url,
+    title,
+    poll_interval_secs,
+    etag,
+    last_modified,
+    last_polled_at,
+    next_poll_at,
+    last_error,
+    created_at
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+ON CONFLICT (url) DO NOTHING
+RETURNING id
+            "#,
+        )
+        .bind(feed.id().into_inner())
+        .bind(feed.url())
+        .bind(feed.title())
+        .bind(poll_interval_secs)
+        .bind(state.etag.as_deref())
+        .bind(state.last_modified.as_deref())
+        .bind(state.last_polled_at)
+        .bind(state.next_poll_at)
+        .bind(state.last_error.as_deref())
+        .bind(feed.created_at())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(id) = inserted_id {
+            return Ok((FeedId::from_raw(id), true));
+        }
+
+        let existing_id: Uuid = sqlx::query_scalar("SELECT id FROM feeds WHERE url = $1")
+            .bind(feed.url())
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((FeedId::from_raw(existing_id), false))
+    }
+
+    pub async fn list_feeds(&self) -> Result<Vec<Feed>> {
+        let rows = sqlx::query(&format!("{FEED_SELECT} ORDER BY created_at ASC"))
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(feed_from_row).collect()
+    }
+
+    /// Feeds whose next poll is at or before `now`, oldest first.
+    pub async fn due_feeds(&self, now: DateTime<Utc>) -> Result<Vec<Feed>> {
+        let rows = sqlx::query(&format!(
+            "{FEED_SELECT} WHERE next_poll_at IS NULL OR next_poll_at <= $1 \
+             ORDER BY next_poll_at ASC NULLS FIRST"
+        ))
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(feed_from_row).collect()
+    }
+
+    pub async fn remove_feed(&self, id: FeedId) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM feeds WHERE id = $1")
+            .bind(id.into_inner())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Stores the outcome of a poll: the feed's new validators and schedule,
+    /// plus a row in the poll history with the number of new observations.
+    /// A `title` of `None` keeps the previously stored title.
+    pub async fn record_feed_poll(
+        &self,
+        feed_id: FeedId,
+        title: Option<&str>,
+        status: FeedPollStatus,
+        new_observations: usize,
+        state: &FeedPollState,
+    ) -> Result<()> {
+        let new_observations = i32::try_from(new_observations)
+            .map_err(|_| StoreError::OutOfRange("new_observations"))?;
+        let polled_at = state.last_polled_at.unwrap_or_else(Utc::now);
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+UPDATE feeds SET
+    etag = $2,
+    last_modified = $3,
+    last_polled_at = $4,
+    next_poll_at = $5,
+    last_error = $6,
+    title = COALESCE($7, title)
+WHERE id = $1
+            "#,
+        )
+        .bind(feed_id.into_inner())
+        .bind(state.etag.as_deref())
+        .bind(state.last_modified.as_deref())
+        .bind(polled_at)
+        .bind(state.next_poll_at)
+        .bind(state.last_error.as_deref())
+        .bind(title)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+INSERT INTO feed_polls (feed_id, polled_at, status, new_observations, error)
+VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(feed_id.into_inner())
+        .bind(polled_at)
+        .bind(status.as_str())
+        .bind(new_observations)
+        .bind(state.last_error.as_deref())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Largest dimension pgvector can build an HNSW index for.
+const MAX_INDEXED_DIMENSION: usize = 2000;
+
+/// Bounds of the HNSW candidate list size used for a search; pgvector's
+/// default is the lower one.
+const MIN_EF_SEARCH: usize = 40;
+const MAX_EF_SEARCH: usize = 1000;
+
+/// `ts_headline` options for keyword search snippets: up to two fragments
+/// of the chunk, with matches marked `**like this**`.
+const HEADLINE_OPTIONS: &str =
+    "StartSel=**, StopSel=**, MaxWords=30, MinWords=12, MaxFragments=2, FragmentDelimiter=\" … \"";
+
+const CHUNK_SELECT: &str = r#"
 SELECT
     id,
     observation_id,
@@ -192,45 +1027,349 @@ SELECT
     text,
     start_offset,
     end_offset,
-    token_estimate
+    token_estimate,
+    heading_path
 FROM chunks
-WHERE observation_id = $1
-ORDER BY chunk_index ASC
-            "#,
-        )
-        .bind(observation_id.into_inner())
-        .fetch_all(&self.pool)
-        .await?;
+"#;
 
-        let mut chunks = Vec::with_capacity(rows.len());
+fn chunk_from_row(row: &PgRow) -> Result<Chunk> {
+    let id: Uuid = row.try_get("id")?;
+    let observation_id: Uuid = row.try_get("observation_id")?;
+    let start_offset: i64 = row.try_get("start_offset")?;
+    let end_offset: i64 = row.try_get("end_offset")?;
+    let token_estimate: i32 = row.try_get("token_estimate")?;
 
-        for row in rows {
-            let id: Uuid = row.try_get("id")?;
-            let observation_id: Uuid = row.try_get("observation_id")?;
-            let chunk_index: i32 = row.try_get("chunk_index")?;
-            let text: String = row.try_get("text")?;
-            let start_offset: i64 = row.try_get("start_offset")?;
-            let end_offset: i64 = row.try_get("end_offset")?;
-            let token_estimate: i32 = row.try_get("token_estimate")?;
+    let start_offset =
+        usize::try_from(start_offset).map_err(|_| StoreError::OutOfRange("start_offset"))?;
+    let end_offset =
+        usize::try_from(end_offset).map_err(|_| StoreError::OutOfRange("end_offset"))?;
+    let token_estimate =
+        u32::try_from(token_estimate).map_err(|_| StoreError::OutOfRange("token_estimate"))?;
 
-            let start_offset = usize::try_from(start_offset)
-                .map_err(|_| StoreError::OutOfRange("start_offset"))?;
-            let end_offset =
-                usize::try_from(end_offset).map_err(|_| StoreError::OutOfRange("end_offset"))?;
-            let token_estimate = u32::try_from(token_estimate)
-                .map_err(|_| StoreError::OutOfRange("token_estimate"))?;
+    Ok(Chunk::reconstruct(
+        ChunkId::from_raw(id),
+        ObservationId::from_raw(observation_id),
+        row.try_get("chunk_index")?,
+        row.try_get("text")?,
+        start_offset,
+        end_offset,
+        token_estimate,
+    )
+    .with_heading_path(row.try_get("heading_path")?))
+}
+
+/// SQL condition applying `filters` to the observation aliased `o`. Its
+/// five parameters start at `$first` and are bound by [`bind_filters`].
+fn filter_conditions(filters: &SearchFilters, first: usize) -> String {
+    let date = match filters.date_field {
+        DateField::Published => "COALESCE(o.published_at, o.created_at)",
+        DateField::Created => "o.created_at",
+    };
+    let [kinds, since, until, domain, title] = std::array::from_fn(|i| first + i);
+
+    // The host is matched with a leading dot so that `example.com` also
+    // matches `news.example.com` but not `badexample.com`.
+    format!(
+        r#"(${kinds}::text[] IS NULL OR o.source_kind = ANY(${kinds}))
+    AND (${since}::timestamptz IS NULL OR {date} >= ${since})
+    AND (${until}::timestamptz IS NULL OR {date} < ${until})
+    AND (${domain}::text IS NULL OR right(
+        '.' || lower(substring(o.source_url FROM '^[A-Za-z][A-Za-z0-9+.-]*://(?:[^@/?#]*@)?([^:/?#]+)')),
+        length(${domain}) + 1
+    ) = '.' || ${domain})
+    AND (${title}::text IS NULL OR o.title ILIKE ${title})"#
+    )
+}
+
+fn bind_filters<'q>(
+    query: Query<'q, Postgres, PgArguments>,
+    filters: &SearchFilters,
+) -> Query<'q, Postgres, PgArguments> {
+    let kinds = (!filters.source_kinds.is_empty()).then(|| {
+        filters
+            .source_kinds
+            .iter()
+            .map(|kind| kind.as_str().to_string())
+            .collect::<Vec<_>>()
+    });
+    let title = filters
+        .title_contains
+        .as_deref()
+        .map(|text| format!("%{}%", escape_like(text)));
 
-            chunks.push(Chunk::reconstruct(
-                ChunkId::from_raw(id),
-                ObservationId::from_raw(observation_id),
-                chunk_index,
-                text,
-                start_offset,
-                end_offset,
-                token_estimate,
-            ));
+    query
+        .bind(kinds)
+        .bind(filters.since)
+        .bind(filters.until)
+        .bind(filters.domain.clone())
+        .bind(title)
+}
+
+/// Escapes the `LIKE` wildcards in `text` so it matches literally.
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
         }
+        escaped.push(c);
+    }
+    escaped
+}
 
-        Ok(chunks)
+fn search_hit_from_row(row: &PgRow) -> Result<SearchHit> {
+    let source_kind: String = row.try_get("source_kind")?;
+
+    Ok(SearchHit {
+        chunk: chunk_from_row(row)?,
+        observation: ObservationSummary {
+            id: ObservationId::from_raw(row.try_get("observation_id")?),
+            title: row.try_get("title")?,
+            source_url: row.try_get("source_url")?,
+            source_kind: SourceKind::parse(&source_kind),
+            created_at: row.try_get("created_at")?,
+            published_at: row.try_get("published_at")?,
+        },
+        score: row.try_get("score")?,
+        snippet: row.try_get("snippet")?,
+    })
+}
+
+/// Text search configuration for content in `language`, a BCP 47 tag.
+/// Languages without a built-in Postgres configuration use `simple`, which
+/// lowercases words without stemming or dropping stop words.
+fn search_config(language: Option<&str>) -> &'static str {
+    let primary = language
+        .and_then(|tag| tag.split(['-', '_']).next())
+        .map(str::to_ascii_lowercase);
+
+    match primary.as_deref() {
+        Some("ar") => "arabic",
+        Some("ca") => "catalan",
+        Some("da") => "danish",
+        Some("de") => "german",
+        Some("el") => "greek",
+        Some("en") => "english",
+        Some("es") => "spanish",
+        Some("eu") => "basque",
+        Some("fi") => "finnish",
+        Some("fr") => "french",
+        Some("ga") => "irish",
+        Some("hi") => "hindi",
+        Some("hu") => "hungarian",
+        Some("hy") => "armenian",
+        Some("id") => "indonesian",
+        Some("it") => "italian",
+        Some("lt") => "lithuanian",
+        Some("ne") => "nepali",
+        Some("nl") => "dutch",
+        Some("nb" | "nn" | "no") => "norwegian",
+        Some("pt") => "portuguese",
+        Some("ro") => "romanian",
+        Some("ru") => "russian",
+        Some("sr") => "serbian",
+        Some("sv") => "swedish",
+        Some("ta") => "tamil",
+        Some("tr") => "turkish",
+        Some("yi") => "yiddish",
+        _ => "simple",
+    }
+}
+
+/// Formats a vector in pgvector's text representation, `[1,2.5,-3]`.
+fn vector_literal(vector: &[f32]) -> Result<String> {
+    let mut literal = String::with_capacity(vector.len() * 10 + 2);
+    literal.push('[');
+    for (i, x) in vector.iter().enumerate() {
+        if !x.is_finite() {
+            return Err(StoreError::InvalidEmbedding("non-finite component"));
+        }
+        if i > 0 {
+            literal.push(',');
+        }
+        literal.push_str(&x.to_string());
+    }
+    literal.push(']');
+    Ok(literal)
+}
+
+/// Reads a vector in pgvector's text form, `[1,2.5,-3]`.
+fn parse_vector(literal: &str) -> Result<Vec<f32>> {
+    let inner = literal
+        .trim()
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or(StoreError::InvalidEmbedding("malformed vector"))?;
+    if inner.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    inner
+        .split(',')
+        .map(|x| {
+            x.trim()
+                .parse()
+                .map_err(|_| StoreError::InvalidEmbedding("malformed vector"))
+        })
+        .collect()
+}
+
+/// `text` as a quoted SQL string literal, for statements that cannot take
+/// bind parameters or whose plans must see the value.
+fn sql_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Name of the HNSW index over `model`'s embeddings. Model names are free
+/// text, so the name is derived from a hash of it.
+fn embedding_index_name(model: &str) -> String {
+    let hash = ContentHash::from_content(model).to_hex();
+    format!("chunk_embeddings_hnsw_{}", &hash[..16])
+}
+
+const FEED_SELECT: &str = r#"
+SELECT
+    id,
+    url,
+    title,
+    poll_interval_secs,
+    etag,
+    last_modified,
+    last_polled_at,
+    next_poll_at,
+    last_error,
+    created_at
+FROM feeds
+"#;
+
+fn feed_from_row(row: &PgRow) -> Result<Feed> {
+    let id: Uuid = row.try_get("id")?;
+    let poll_interval_secs: i64 = row.try_get("poll_interval_secs")?;
+    let poll_interval_secs = u64::try_from(poll_interval_secs)
+        .map_err(|_| StoreError::OutOfRange("poll_interval_secs"))?;
+
+    let state = FeedPollState {
+        etag: row.try_get("etag")?,
+        last_modified: row.try_get("last_modified")?,
+        last_polled_at: row.try_get("last_polled_at")?,
+        next_poll_at: row.try_get("next_poll_at")?,
+        last_error: row.try_get("last_error")?,
+    };
+
+    Ok(Feed::reconstruct(
+        FeedId::from_raw(id),
+        row.try_get("url")?,
+        row.try_get("title")?,
+        Duration::from_secs(poll_interval_secs),
+        row.try_get("created_at")?,
+        state,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+
+    #[test]
+    fn escape_like_escapes_wildcards_and_backslashes() {
+        assert_eq!(escape_like(r"50%_off\now"), r"50\%\_off\\now");
+        assert_eq!(escape_like("plain"), "plain");
+    }
+
+    /// Source URLs of the sample observations that pass `filters`.
+    async fn matching_urls(pool: &PgPool, filters: &SearchFilters) -> Vec<String> {
+        // Each URL ends in a short name the cases below refer to.
+        let query = format!(
+            r#"
+SELECT o.source_url
+FROM (VALUES
+    ('https://example.com/a', 'web', 'Crabs', NULL::timestamptz, '2026-01-10'::timestamptz),
+    ('https://news.example.com/b', 'rss', '50% off shells', '2026-02-10', '2026-01-01'),
+    ('http://user@WWW.Example.COM:8080/c', 'web', '500 shells', NULL, '2026-03-10'),
+    ('https://badexample.com/d', 'pdf', 'Crab_walk', NULL, '2026-01-10'),
+    ('https://example.com.evil.net/e', 'web', NULL, NULL, '2026-01-10'),
+    (NULL, 'text', 'Pasted', NULL, '2026-01-10')
+) AS o (source_url, source_kind, title, published_at, created_at)
+WHERE {}
+            "#,
+            filter_conditions(filters, 1)
+        );
+        let rows = bind_filters(sqlx::query(&query), filters)
+            .fetch_all(pool)
+            .await
+            .unwrap();
+        let mut names: Vec<String> = rows
+            .iter()
+            .map(|row| row.get::<Option<String>, _>(0).unwrap_or_default())
+            .map(|url| url.rsplit('/').next().unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server at DATABASE_URL"]
+    async fn filters_match_in_sql() {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL is set");
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&url)
+            .await
+            .unwrap();
+        let date = |month| Utc.with_ymd_and_hms(2026, month, 1, 0, 0, 0).unwrap();
+        let cases = [
+            (SearchFilters::default(), vec!["", "a", "b", "c", "d", "e"]),
+            (
+                SearchFilters::default().with_domain("example.com"),
+                vec!["a", "b", "c"],
+            ),
+            (
+                SearchFilters::default().with_domain("news.example.com"),
+                vec!["b"],
+            ),
+            (
+                SearchFilters::default().with_title_contains("50%"),
+                vec!["b"],
+            ),
+            (
+                SearchFilters::default().with_title_contains("b_w"),
+                vec!["d"],
+            ),
+            (SearchFilters::default().with_title_contains("f_s"), vec![]),
+            (
+                SearchFilters::default().with_title_contains("CRAB"),
+                vec!["a", "d"],
+            ),
+            (
+                SearchFilters::default()
+                    .with_source_kind(SourceKind::Pdf)
+                    .with_source_kind(SourceKind::Text),
+                vec!["", "d"],
+            ),
+            (
+                SearchFilters::default().with_source_kind(SourceKind::Rss),
+                vec!["b"],
+            ),
+            // Published dates fall back to the ingestion date.
+            (
+                SearchFilters::default()
+                    .with_since(date(2))
+                    .with_until(date(4)),
+                vec!["b", "c"],
+            ),
+            (
+                SearchFilters::default()
+                    .with_date_field(DateField::Created)
+                    .with_until(date(2)),
+                vec!["", "a", "b", "d", "e"],
+            ),
+        ];
+
+        for (filters, expected) in cases {
+            let urls = matching_urls(&pool, &filters).await;
+            assert_eq!(urls, expected, "{filters:?}");
+        }
     }
 }